@@ -0,0 +1,133 @@
+//! Times the requests `deployc tier upgrade` makes (the app's tier, whether
+//! there is a payment method, then the tiers) against a local mock server,
+//! with one shared client and with a new client per request.
+//!
+//! Run with `cargo bench`. The mock server is plain HTTP, so this leaves out
+//! the TLS handshake a new connection to deployc.io also pays for.
+
+extern crate deployc;
+extern crate url;
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use deployc::api::ApiClient;
+use url::Url;
+
+const ITERATIONS: u32 = 50;
+const APP: &str = "bench";
+
+fn body(path: &str) -> Option<&'static str> {
+    match path {
+        "/api/apps/bench/tier/" => Some(r#"{"name":"free","pricing":"$0/month"}"#),
+        "/api/has-card" => Some(r#"{"exists":true}"#),
+        "/api/tiers" => Some(
+            r#"{"tiers":[{"name":"free","pricing":"$0/month"},{"name":"pro","pricing":"$7/month"}]}"#,
+        ),
+        _ => None,
+    }
+}
+
+/// Answers requests on a connection until the client closes it.
+fn serve(stream: TcpStream) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut writer = stream;
+    loop {
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).unwrap_or(0) == 0 {
+            return;
+        }
+        let path = request_line
+            .split_whitespace()
+            .nth(1)
+            .unwrap_or("/")
+            .to_string();
+
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header).unwrap_or(0) == 0 {
+                return;
+            }
+            if header.trim().is_empty() {
+                break;
+            }
+            if let Some(len) = header.to_lowercase().strip_prefix("content-length:") {
+                content_length = len.trim().parse().unwrap();
+            }
+        }
+        let mut request_body = vec![0; content_length];
+        if reader.read_exact(&mut request_body).is_err() {
+            return;
+        }
+
+        let (status, body) = match body(&path) {
+            Some(body) => ("200 OK", body),
+            None => ("404 Not Found", r#"{"error":"Not found."}"#),
+        };
+        // in one write, so the response isn't held up by Nagle's algorithm
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        if writer.write_all(response.as_bytes()).is_err() {
+            return;
+        }
+    }
+}
+
+/// Starts the mock server, returning its API endpoint.
+fn start_server() -> Url {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for stream in listener.incoming().filter_map(|stream| stream.ok()) {
+            thread::spawn(move || serve(stream));
+        }
+    });
+    Url::parse(&format!("http://{}/api/", addr)).unwrap()
+}
+
+fn millis(elapsed: Duration) -> f64 {
+    elapsed.as_secs() as f64 * 1000.0 + f64::from(elapsed.subsec_nanos()) / 1_000_000.0
+}
+
+fn bench<F: FnMut()>(name: &str, mut command: F) -> Duration {
+    // the first run also connects to the server
+    command();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        command();
+    }
+    let elapsed = start.elapsed() / ITERATIONS;
+    println!("{:<24} {:>8.2}ms per command", name, millis(elapsed));
+    elapsed
+}
+
+fn tier_upgrade(client: &ApiClient) {
+    client.tier(APP).unwrap();
+    assert!(client.has_card().unwrap());
+    client.tiers().unwrap();
+}
+
+fn main() {
+    let endpoint = start_server();
+    let client = || ApiClient::new(endpoint.clone()).with_token("bench".to_string());
+
+    let shared = client();
+    let pooled = bench("shared client", || tier_upgrade(&shared));
+    let unpooled = bench("client per request", || {
+        client().tier(APP).unwrap();
+        assert!(client().has_card().unwrap());
+        client().tiers().unwrap();
+    });
+    println!(
+        "{:<24} {:>8.1}x",
+        "speedup",
+        millis(unpooled) / millis(pooled)
+    );
+}
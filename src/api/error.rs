@@ -0,0 +1,198 @@
+use std::error;
+use std::fmt;
+use std::io;
+use std::time::{Duration, SystemTime};
+
+use reqwest::header::RetryAfter;
+use reqwest::{self, Response, StatusCode};
+
+header! { (XRequestId, "X-Request-Id") => [String] }
+
+/// The body of an error response.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct APIError {
+    pub error: String,
+    /// Machine readable reason, for errors the CLI can act on.
+    #[serde(default)]
+    pub code: Option<String>,
+    #[serde(default)]
+    pub fields: Vec<FieldError>,
+}
+
+/// A problem with one field of a request, for validation errors.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// An error response from the API.
+#[derive(Debug)]
+pub struct ErrorResponse {
+    pub status: StatusCode,
+    pub request_id: Option<String>,
+    pub error: APIError,
+}
+
+/// An API request that failed, classified by what the caller can do about it.
+#[derive(Debug)]
+pub enum RequestError {
+    /// The request could not be sent, or the response could not be read.
+    Network(reqwest::Error),
+    /// The connection settings can't be used, such as an unreadable CA bundle.
+    Config(String),
+    /// A file to send with the request could not be read.
+    Body(io::Error),
+    /// 401 or 403.
+    Auth(ErrorResponse),
+    NotFound(ErrorResponse),
+    /// 400 or 422, usually with field errors.
+    Validation(ErrorResponse),
+    RateLimited {
+        response: ErrorResponse,
+        retry_after_secs: Option<u64>,
+    },
+    /// Any 5xx.
+    Server(ErrorResponse),
+    /// Any other 4xx.
+    Client(ErrorResponse),
+    /// The token was rejected and could not be renewed, see
+    /// `ApiClient::on_unauthorized`.
+    Renew(Box<dyn error::Error + Send + Sync>),
+    /// The server no longer accepts the API version this client speaks.
+    UnsupportedVersion {
+        version: String,
+        supported: Vec<String>,
+    },
+    /// The response was not what the CLI expected.
+    Parse {
+        status: StatusCode,
+        request_id: Option<String>,
+        detail: String,
+    },
+}
+
+pub fn request_id(res: &Response) -> Option<String> {
+    res.headers().get::<XRequestId>().map(|id| id.0.clone())
+}
+
+/// How long the server asked to wait before trying again.
+pub fn retry_after(res: &Response) -> Option<Duration> {
+    match res.headers().get::<RetryAfter>() {
+        Some(&RetryAfter::Delay(delay)) => Some(delay),
+        Some(&RetryAfter::DateTime(date)) => Some(
+            SystemTime::from(date)
+                .duration_since(SystemTime::now())
+                .unwrap_or_default(),
+        ),
+        None => None,
+    }
+}
+
+impl RequestError {
+    /// Classifies an unsuccessful response.
+    pub fn from_response(mut res: Response) -> RequestError {
+        let status = res.status();
+        let request_id = request_id(&res);
+        let retry_after_secs = retry_after(&res).map(|delay| delay.as_secs());
+
+        let error = match res.json() {
+            Ok(error) => error,
+            Err(err) => {
+                return RequestError::Parse {
+                    status,
+                    request_id,
+                    detail: format!("{}", err),
+                }
+            }
+        };
+        let response = ErrorResponse {
+            status,
+            request_id,
+            error,
+        };
+
+        match status {
+            StatusCode::Unauthorized | StatusCode::Forbidden => RequestError::Auth(response),
+            StatusCode::NotFound => RequestError::NotFound(response),
+            StatusCode::BadRequest | StatusCode::UnprocessableEntity => {
+                RequestError::Validation(response)
+            }
+            StatusCode::TooManyRequests => RequestError::RateLimited {
+                response,
+                retry_after_secs,
+            },
+            _ if status.is_server_error() => RequestError::Server(response),
+            _ => RequestError::Client(response),
+        }
+    }
+
+    pub fn response(&self) -> Option<&ErrorResponse> {
+        match self {
+            RequestError::Auth(response)
+            | RequestError::NotFound(response)
+            | RequestError::Validation(response)
+            | RequestError::RateLimited { response, .. }
+            | RequestError::Server(response)
+            | RequestError::Client(response) => Some(response),
+            RequestError::Network(_)
+            | RequestError::Config(_)
+            | RequestError::Body(_)
+            | RequestError::Renew(_)
+            | RequestError::UnsupportedVersion { .. }
+            | RequestError::Parse { .. } => None,
+        }
+    }
+
+    pub fn request_id(&self) -> Option<&str> {
+        match self {
+            RequestError::Parse { request_id, .. } => request_id.as_deref(),
+            _ => self
+                .response()
+                .and_then(|response| response.request_id.as_deref()),
+        }
+    }
+
+    /// The machine readable error code from the server, if any.
+    pub fn code(&self) -> Option<&str> {
+        self.response()
+            .and_then(|response| response.error.code.as_deref())
+    }
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RequestError::Network(err) => write!(f, "{}", err),
+            RequestError::Config(message) => write!(f, "{}", message),
+            RequestError::Body(err) => write!(f, "Could not read request body: {}", err),
+            RequestError::Renew(err) => write!(f, "{}", err),
+            RequestError::UnsupportedVersion { version, supported } => {
+                write!(f, "The server no longer supports API version {}", version)?;
+                if !supported.is_empty() {
+                    write!(f, " (it supports {})", supported.join(", "))?;
+                }
+                write!(f, ".")
+            }
+            RequestError::Parse { status, detail, .. } => {
+                write!(f, "Failed to parse response ({}): {}", status, detail)
+            }
+            _ => {
+                let response = self.response().unwrap();
+                write!(f, "{}", response.error.error)?;
+                for field in &response.error.fields {
+                    write!(f, "\n  {}: {}", field.field, field.message)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl error::Error for RequestError {}
+
+impl From<reqwest::Error> for RequestError {
+    fn from(err: reqwest::Error) -> RequestError {
+        RequestError::Network(err)
+    }
+}
@@ -0,0 +1,710 @@
+//! A typed client for the deployc.io API.
+
+use std::cmp;
+use std::collections::HashMap;
+use std::error::Error;
+use std::io;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use rand::{thread_rng, Rng, RngCore};
+use reqwest::header::{Authorization, Bearer};
+use reqwest::multipart::Form;
+use reqwest::{self, Method, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use serde_json::{self, Value};
+use url::Url;
+
+use app::{App, EnvVar, Secret, SecretType};
+use models::{
+    ApiToken, CreatedApp, CreatedToken, DeviceCode, Session, Signup, Tier, TokenResponse, User,
+};
+
+mod error;
+mod options;
+mod proxy;
+mod tls;
+mod trace;
+mod version;
+
+pub use self::error::{APIError, ErrorResponse, FieldError, RequestError};
+pub use self::options::RequestOptions;
+pub use self::tls::TlsOptions;
+pub use self::version::{Compatibility, ServerVersion};
+
+/// The version of the API this client speaks.
+pub const API_VERSION: &str = "2018-06-01";
+const INITIAL_BACKOFF_MS: u64 = 500;
+const MAX_BACKOFF_MS: u64 = 8000;
+// a longer wait than this is reported instead
+const MAX_RETRY_AFTER_SECS: u64 = 60;
+
+header! { (XAPIVersion, "X-API-Version") => [String] }
+header! { (IdempotencyKey, "Idempotency-Key") => [String] }
+
+#[derive(Deserialize)]
+struct Message {
+    #[allow(dead_code)]
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct Apps {
+    apps: Vec<App>,
+}
+
+#[derive(Deserialize)]
+struct Env {
+    env: HashMap<String, EnvVar>,
+}
+
+#[derive(Deserialize)]
+struct Secrets {
+    secrets: Vec<Secret>,
+}
+
+#[derive(Deserialize)]
+struct Tiers {
+    tiers: Vec<Tier>,
+}
+
+#[derive(Deserialize)]
+struct HasCard {
+    exists: bool,
+}
+
+#[derive(Deserialize)]
+struct Sessions {
+    sessions: Vec<Session>,
+}
+
+#[derive(Deserialize)]
+struct Tokens {
+    tokens: Vec<ApiToken>,
+}
+
+#[derive(Deserialize)]
+struct CurrentToken {
+    name: String,
+}
+
+/// Why the token could not be renewed, passed on to the caller as is.
+type RenewError = Box<dyn Error + Send + Sync>;
+/// Called when a request is unauthorized, to get a new token to retry it with.
+type RenewFn = dyn Fn() -> Result<String, RenewError> + Send + Sync;
+/// Called before a failed request is retried, with why it failed and how long
+/// until it is retried.
+type RetryFn = dyn Fn(&str, Duration) + Send + Sync;
+
+/// HTTP clients, built on first use and then shared by every request so
+/// that connections to the server are kept alive and reused.
+#[derive(Default)]
+struct Pool {
+    requests: Mutex<Option<reqwest::Client>>,
+    /// Without a read timeout, for streamed responses.
+    streams: Mutex<Option<reqwest::Client>>,
+}
+
+/// A client for the API at `endpoint`, with one method per endpoint.
+///
+/// Requests are retried with backoff when they fail in passing, as far as
+/// `RequestOptions` allows and it is safe to: see `RequestOptions::retries`.
+/// Make one client and reuse it, as each client keeps its own connections.
+pub struct ApiClient {
+    endpoint: Url,
+    token: Mutex<String>,
+    options: RequestOptions,
+    tls: TlsOptions,
+    renew: Option<Box<RenewFn>>,
+    on_retry: Option<Box<RetryFn>>,
+    pool: Pool,
+    server_version: Mutex<Option<ServerVersion>>,
+}
+
+impl ApiClient {
+    /// A client without a token, for the API rooted at `endpoint`, e.g.
+    /// `https://deployc.io/api/`.
+    pub fn new(endpoint: Url) -> ApiClient {
+        ApiClient {
+            endpoint,
+            token: Mutex::new("".to_string()),
+            options: RequestOptions::default(),
+            tls: TlsOptions::default(),
+            renew: None,
+            on_retry: None,
+            pool: Pool::default(),
+            server_version: Mutex::new(None),
+        }
+    }
+
+    /// Authenticates requests with a session or API token.
+    pub fn with_token(self, token: String) -> ApiClient {
+        ApiClient {
+            token: Mutex::new(token),
+            ..self
+        }
+    }
+
+    pub fn with_options(self, options: RequestOptions) -> ApiClient {
+        ApiClient {
+            options,
+            pool: Pool::default(),
+            ..self
+        }
+    }
+
+    pub fn with_tls(self, tls: TlsOptions) -> ApiClient {
+        ApiClient {
+            tls,
+            pool: Pool::default(),
+            ..self
+        }
+    }
+
+    /// Renews the token when a request is unauthorized: `renew` returns a new
+    /// token to retry the request with, or why it couldn't get one, which the
+    /// request fails with as `RequestError::Renew`.
+    pub fn on_unauthorized<F, E>(self, renew: F) -> ApiClient
+    where
+        F: Fn() -> Result<String, E> + Send + Sync + 'static,
+        E: Into<RenewError>,
+    {
+        ApiClient {
+            renew: Some(Box::new(move || renew().map_err(Into::into))),
+            ..self
+        }
+    }
+
+    /// Reports requests that are about to be retried, e.g. to tell the user
+    /// why a command is taking a while.
+    pub fn on_retry<F>(self, on_retry: F) -> ApiClient
+    where
+        F: Fn(&str, Duration) + Send + Sync + 'static,
+    {
+        ApiClient {
+            on_retry: Some(Box::new(on_retry)),
+            ..self
+        }
+    }
+
+    /// The token requests are currently made with, which changes when it is renewed.
+    pub fn token(&self) -> String {
+        self.token.lock().unwrap().clone()
+    }
+
+    /// The API versions the server accepts, as of its last response. `None`
+    /// before the first response, or if the server doesn't say.
+    pub fn server_version(&self) -> Option<ServerVersion> {
+        self.server_version.lock().unwrap().clone()
+    }
+
+    /// The pooled HTTP client for requests, or for streams.
+    fn http(&self, stream: bool) -> Result<reqwest::Client, RequestError> {
+        let (pooled, timeout) = if stream {
+            (&self.pool.streams, None)
+        } else {
+            (&self.pool.requests, self.options.read_timeout)
+        };
+        let mut pooled = pooled.lock().unwrap();
+        if let Some(ref client) = *pooled {
+            return Ok(client.clone());
+        }
+
+        let mut client = reqwest::Client::builder();
+        client.timeout(timeout);
+        // this version of reqwest doesn't read the proxy variables itself
+        if proxy::proxy_for(&self.endpoint)?.is_some() {
+            client.proxy(reqwest::Proxy::custom(|url| {
+                proxy::proxy_for(url).unwrap_or(None)
+            }));
+        }
+        tls::configure(&mut client, &self.tls)?;
+        let client = client.build()?;
+        *pooled = Some(client.clone());
+        Ok(client)
+    }
+
+    fn request(&self, path: &str) -> ApiRequest<'_> {
+        ApiRequest {
+            client: self,
+            route: self.endpoint.join(path).unwrap(),
+            renew: true,
+            idempotency_key: None,
+        }
+    }
+
+    fn app(&self, name: &str) -> ApiRequest<'_> {
+        self.request("apps/").param(name)
+    }
+
+    pub fn apps(&self) -> Result<Vec<App>, RequestError> {
+        let Apps { apps } = self.request("apps/").get()?;
+        Ok(apps)
+    }
+
+    pub fn create_app(&self, name: &str) -> Result<CreatedApp, RequestError> {
+        self.request("apps/")
+            .idempotent()
+            .post(&json!({ "name": name }))
+    }
+
+    pub fn delete_app(&self, name: &str) -> Result<(), RequestError> {
+        let Message { .. } = self.app(name).delete()?;
+        Ok(())
+    }
+
+    pub fn env(&self, app: &str) -> Result<HashMap<String, EnvVar>, RequestError> {
+        let Env { env } = self.app(app).param("env").get()?;
+        Ok(env)
+    }
+
+    /// Sets an environment variable, returning the app's environment.
+    pub fn set_env(
+        &self,
+        app: &str,
+        key: &str,
+        var: &EnvVar,
+    ) -> Result<HashMap<String, EnvVar>, RequestError> {
+        let Env { env } = self
+            .app(app)
+            .param("env")
+            .idempotent()
+            .post(&json!({ "key": key, "value": var }))?;
+        Ok(env)
+    }
+
+    pub fn secrets(&self, app: &str) -> Result<Vec<Secret>, RequestError> {
+        let Secrets { secrets } = self.app(app).param("secrets").get()?;
+        Ok(secrets)
+    }
+
+    /// Creates a secret from its value, whose shape depends on its type.
+    pub fn create_secret(
+        &self,
+        app: &str,
+        name: &str,
+        ty: &SecretType,
+        value: &Value,
+    ) -> Result<Secret, RequestError> {
+        let body = json!({
+            "name": name,
+            "type": ty,
+            "value": value
+        });
+        self.app(app).param("secrets").idempotent().post(&body)
+    }
+
+    pub fn tier(&self, app: &str) -> Result<Tier, RequestError> {
+        self.app(app).param("tier").get()
+    }
+
+    pub fn tiers(&self) -> Result<Vec<Tier>, RequestError> {
+        let Tiers { tiers } = self.request("tiers").get()?;
+        Ok(tiers)
+    }
+
+    /// Whether the account has a payment method, which apps need.
+    pub fn has_card(&self) -> Result<bool, RequestError> {
+        let HasCard { exists } = self.request("has-card").get()?;
+        Ok(exists)
+    }
+
+    /// Streams an app's logs. `query` selects them, e.g. `tail` or `follow`.
+    pub fn logs(&self, app: &str, query: &[(&str, String)]) -> Result<Response, RequestError> {
+        self.app(app).param("logs").stream(Method::Get, |req| {
+            req.query(query);
+            Ok(())
+        })
+    }
+
+    /// Deploys an app from a form holding its files, streaming the deploy's
+    /// progress. `form` makes the form, again if the session had to be renewed.
+    pub fn up<F>(&self, app: &str, form: F) -> Result<Response, RequestError>
+    where
+        F: Fn() -> io::Result<Form>,
+    {
+        self.app(app).param("up").stream(Method::Post, |req| {
+            req.multipart(form()?);
+            Ok(())
+        })
+    }
+
+    pub fn me(&self) -> Result<User, RequestError> {
+        self.request("me").get()
+    }
+
+    pub fn signup(
+        &self,
+        username: &str,
+        email: &str,
+        password: &str,
+    ) -> Result<Signup, RequestError> {
+        let body = json!({
+            "username": username,
+            "email": email,
+            "password": password
+        });
+        self.request("signup").without_renewal().post(&body)
+    }
+
+    /// Logs in with a password, and a two-factor code if the account needs
+    /// one, which the server asks for with the error code `otp_required`.
+    pub fn login(
+        &self,
+        username: &str,
+        password: &str,
+        otp: Option<&str>,
+    ) -> Result<TokenResponse, RequestError> {
+        let mut body = json!({
+            "username": username,
+            "password": password
+        });
+        if let Some(otp) = otp {
+            body["otp"] = json!(otp);
+        }
+        self.request("login").without_renewal().post(&body)
+    }
+
+    /// Starts a device login, which the user approves in a browser.
+    pub fn device_code(&self) -> Result<DeviceCode, RequestError> {
+        self.request("login/device")
+            .without_renewal()
+            .post(&json!({}))
+    }
+
+    /// Polls for the token of a device login. Fails with the error code
+    /// `authorization_pending` until the user approves it, or `slow_down`
+    /// when polled too often.
+    pub fn device_token(&self, device_code: &str) -> Result<TokenResponse, RequestError> {
+        self.request("login/device/token")
+            .without_renewal()
+            .post(&json!({ "deviceCode": device_code }))
+    }
+
+    /// Gets a new token for the session, if it hasn't expired.
+    pub fn refresh(&self) -> Result<TokenResponse, RequestError> {
+        self.request("refresh").without_renewal().get()
+    }
+
+    /// Revokes the client's session.
+    pub fn logout(&self) -> Result<(), RequestError> {
+        let Message { .. } = self
+            .request("logout")
+            .without_renewal()
+            .idempotent()
+            .post(&json!({}))?;
+        Ok(())
+    }
+
+    pub fn sessions(&self) -> Result<Vec<Session>, RequestError> {
+        let Sessions { sessions } = self.request("sessions/").get()?;
+        Ok(sessions)
+    }
+
+    pub fn revoke_session(&self, id: &str) -> Result<(), RequestError> {
+        let Message { .. } = self.request("sessions/").param(id).delete()?;
+        Ok(())
+    }
+
+    pub fn tokens(&self) -> Result<Vec<ApiToken>, RequestError> {
+        let Tokens { tokens } = self.request("tokens/").get()?;
+        Ok(tokens)
+    }
+
+    /// The name of the API token the client is using.
+    pub fn current_token(&self) -> Result<String, RequestError> {
+        let CurrentToken { name } = self.request("tokens/").param("current").get()?;
+        Ok(name)
+    }
+
+    pub fn create_token(
+        &self,
+        name: &str,
+        scopes: &[&str],
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<CreatedToken, RequestError> {
+        let body = json!({
+            "name": name,
+            "scopes": scopes,
+            "expiresAt": expires_at
+        });
+        self.request("tokens/").idempotent().post(&body)
+    }
+
+    pub fn revoke_token(&self, name: &str) -> Result<(), RequestError> {
+        let Message { .. } = self.request("tokens/").param(name).delete()?;
+        Ok(())
+    }
+}
+
+struct ApiRequest<'a> {
+    client: &'a ApiClient,
+    route: Url,
+    renew: bool,
+    idempotency_key: Option<String>,
+}
+
+/// Whether a failed request can safely be sent again.
+fn is_transient(err: &reqwest::Error) -> bool {
+    err.is_http()
+        || err
+            .get_ref()
+            .and_then(|err| err.downcast_ref::<io::Error>())
+            .is_some()
+}
+
+/// Exponential backoff with jitter, so that clients retrying together spread out.
+fn backoff(attempt: u32) -> Duration {
+    let max = cmp::min(
+        INITIAL_BACKOFF_MS.saturating_mul(1 << cmp::min(attempt, 16)),
+        MAX_BACKOFF_MS,
+    );
+    Duration::from_millis(thread_rng().gen_range(max / 2, max + 1))
+}
+
+fn is_gateway_error(status: StatusCode) -> bool {
+    status == StatusCode::BadGateway
+        || status == StatusCode::ServiceUnavailable
+        || status == StatusCode::GatewayTimeout
+}
+
+impl<'a> ApiRequest<'a> {
+    /// Don't try to renew the session when this request is unauthorized, for
+    /// the requests that renewing is made of.
+    fn without_renewal(self) -> ApiRequest<'a> {
+        ApiRequest {
+            renew: false,
+            ..self
+        }
+    }
+
+    /// Sends an `Idempotency-Key` with a POST, so that it can be retried
+    /// without the server acting on it twice.
+    fn idempotent(self) -> ApiRequest<'a> {
+        let mut key = [0u8; 16];
+        thread_rng().fill_bytes(&mut key);
+        ApiRequest {
+            idempotency_key: Some(key.iter().map(|b| format!("{:02x}", b)).collect()),
+            ..self
+        }
+    }
+
+    fn set_headers(&self, req: &mut RequestBuilder) {
+        req.header(XAPIVersion(API_VERSION.to_owned()));
+        let token = self.client.token();
+        if !token.is_empty() {
+            req.header(Authorization(Bearer { token }));
+        }
+        if let Some(ref key) = self.idempotency_key {
+            req.header(IdempotencyKey(key.clone()));
+        }
+    }
+
+    fn execute(
+        &self,
+        client: &reqwest::Client,
+        mut req: RequestBuilder,
+        body: Option<&Value>,
+    ) -> Result<Response, RequestError> {
+        self.set_headers(&mut req);
+        let req = req.build()?;
+        let verbose = self.client.options.verbose;
+        if verbose {
+            trace::request(&req, body);
+        }
+
+        let start = Instant::now();
+        let res = client.execute(req);
+        if verbose {
+            match res {
+                Ok(ref res) => trace::response(res, start.elapsed()),
+                Err(ref err) => trace::failure(err, start.elapsed()),
+            }
+        }
+        let res = res?;
+
+        if let Some(server_version) = ServerVersion::from_response(&res) {
+            let compatibility = server_version.compatibility(API_VERSION);
+            *self.client.server_version.lock().unwrap() = Some(server_version.clone());
+            if compatibility == Compatibility::Unsupported {
+                return Err(RequestError::UnsupportedVersion {
+                    version: API_VERSION.to_string(),
+                    supported: server_version.supported,
+                });
+            }
+        }
+        Ok(res)
+    }
+
+    fn send_once<F>(
+        &self,
+        client: &reqwest::Client,
+        body: Option<&Value>,
+        build: &F,
+    ) -> Result<Response, RequestError>
+    where
+        F: Fn(&reqwest::Client) -> RequestBuilder,
+    {
+        let res = self.execute(client, build(client), body)?;
+
+        // the token may have expired since the client was made, so renew it and try again
+        if res.status() == StatusCode::Unauthorized && self.renew_token()? {
+            return self.execute(client, build(client), body);
+        }
+
+        Ok(res)
+    }
+
+    /// Renews the token after a 401, returning whether there is a new one to
+    /// send the request again with.
+    fn renew_token(&self) -> Result<bool, RequestError> {
+        match self.client.renew {
+            Some(ref renew) if self.renew => {
+                let token = renew().map_err(RequestError::Renew)?;
+                *self.client.token.lock().unwrap() = token;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Sends a request, retrying it with backoff when it failed in a way that
+    /// might not happen again. Only idempotent requests are retried after
+    /// connection errors and gateway errors, as those may have reached the
+    /// server, but rate limited requests never did.
+    fn send<F>(
+        &self,
+        method: Method,
+        body: Option<&Value>,
+        build: &F,
+    ) -> Result<Response, RequestError>
+    where
+        F: Fn(&reqwest::Client) -> RequestBuilder,
+    {
+        let client = self.client.http(false)?;
+        let idempotent = match method {
+            Method::Get | Method::Delete => true,
+            _ => self.idempotency_key.is_some(),
+        };
+
+        let mut attempt = 0;
+        loop {
+            let res = self.send_once(&client, body, build);
+            if attempt >= self.client.options.retries {
+                return res;
+            }
+
+            let retry = match res {
+                Err(RequestError::Network(ref err)) if idempotent && is_transient(err) => {
+                    Some((format!("{}", err), backoff(attempt)))
+                }
+                Ok(ref r) if r.status() == StatusCode::TooManyRequests => {
+                    match error::retry_after(r) {
+                        // not worth waiting for, so leave it to the user
+                        Some(delay) if delay > Duration::from_secs(MAX_RETRY_AFTER_SECS) => None,
+                        delay => Some((
+                            format!("{}", r.status()),
+                            delay.unwrap_or_else(|| backoff(attempt)),
+                        )),
+                    }
+                }
+                Ok(ref r) if idempotent && is_gateway_error(r.status()) => {
+                    Some((format!("{}", r.status()), backoff(attempt)))
+                }
+                _ => None,
+            };
+            let (reason, delay) = match retry {
+                Some(retry) => retry,
+                None => return res,
+            };
+
+            if let Some(ref on_retry) = self.client.on_retry {
+                on_retry(&reason, delay);
+            }
+            thread::sleep(delay);
+            attempt += 1;
+        }
+    }
+
+    fn get_response<R, F>(
+        &self,
+        method: Method,
+        body: Option<&Value>,
+        build: F,
+    ) -> Result<R, RequestError>
+    where
+        R: DeserializeOwned,
+        F: Fn(&reqwest::Client) -> RequestBuilder,
+    {
+        let mut res = self.send(method, body, &build)?;
+        if !res.status().is_success() {
+            return Err(RequestError::from_response(res));
+        }
+
+        let status = res.status();
+        let request_id = error::request_id(&res);
+        res.json().map_err(|err| RequestError::Parse {
+            status,
+            request_id,
+            detail: format!("{}", err),
+        })
+    }
+
+    fn param(self, p: &str) -> ApiRequest<'a> {
+        ApiRequest {
+            route: self.route.join(&format!("{}/", p)).unwrap(),
+            ..self
+        }
+    }
+
+    fn get<R: DeserializeOwned>(&self) -> Result<R, RequestError> {
+        self.get_response(Method::Get, None, |client| client.get(self.route.clone()))
+    }
+
+    fn post<T: Serialize + ?Sized, R: DeserializeOwned>(
+        &self,
+        json: &T,
+    ) -> Result<R, RequestError> {
+        // only used for tracing, a body that can't be serialized fails when sent
+        let body = serde_json::to_value(json).ok();
+        self.get_response(Method::Post, body.as_ref(), |client| {
+            let mut req = client.post(self.route.clone());
+            req.json(json);
+            req
+        })
+    }
+
+    fn delete<R: DeserializeOwned>(&self) -> Result<R, RequestError> {
+        self.get_response(Method::Delete, None, |client| {
+            client.delete(self.route.clone())
+        })
+    }
+
+    /// Sends a request for a streamed response, which is read without a
+    /// timeout and only sent again after renewing the session. `build` adds
+    /// anything else the request needs, each time it is sent.
+    fn stream<F>(&self, method: Method, build: F) -> Result<Response, RequestError>
+    where
+        F: Fn(&mut RequestBuilder) -> io::Result<()>,
+    {
+        let client = self.client.http(true)?;
+        let send = || {
+            let mut req = client.request(method.clone(), self.route.clone());
+            build(&mut req).map_err(RequestError::Body)?;
+            self.execute(&client, req, None)
+        };
+
+        let mut res = send()?;
+        // before the body is read, so nothing has been shown yet
+        if res.status() == StatusCode::Unauthorized && self.renew_token()? {
+            res = send()?;
+        }
+        if !res.status().is_success() {
+            return Err(RequestError::from_response(res));
+        }
+        Ok(res)
+    }
+}
@@ -0,0 +1,31 @@
+use std::time::Duration;
+
+const DEFAULT_READ_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_RETRIES: u32 = 3;
+
+/// Timeouts, retries and tracing for API requests.
+///
+/// There is no separate connect timeout: this version of reqwest can't set
+/// one, so connecting is only bounded by `read_timeout`, and not at all for
+/// streams.
+#[derive(Clone)]
+pub struct RequestOptions {
+    /// How long to wait for a response, including connecting, and then for
+    /// each read of its body. Streams (`logs` and `up`) are not read with a
+    /// timeout, as they can be quiet for a long time.
+    pub read_timeout: Option<Duration>,
+    /// How many times to retry idempotent requests that failed in passing.
+    pub retries: u32,
+    /// Logs every request and response to stderr, without credentials.
+    pub verbose: bool,
+}
+
+impl Default for RequestOptions {
+    fn default() -> RequestOptions {
+        RequestOptions {
+            read_timeout: Some(Duration::from_secs(DEFAULT_READ_TIMEOUT_SECS)),
+            retries: DEFAULT_RETRIES,
+            verbose: false,
+        }
+    }
+}
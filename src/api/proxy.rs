@@ -0,0 +1,52 @@
+use std::env;
+
+use url::Url;
+
+use super::RequestError;
+
+/// Reads the first of the variables that is set, preferring lowercase like curl does.
+fn var(names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+}
+
+/// Checks `host` against `NO_PROXY`, a comma separated list of hosts and
+/// domains. `*` turns the proxy off for every host.
+fn bypass(host: &str) -> bool {
+    let no_proxy = match var(&["no_proxy", "NO_PROXY"]) {
+        Some(no_proxy) => no_proxy,
+        None => return false,
+    };
+
+    no_proxy.split(',').map(|entry| entry.trim()).any(|entry| {
+        // ports are ignored, as is the leading dot some lists use for domains
+        let entry = entry.split(':').next().unwrap().trim_start_matches('.');
+        entry == "*"
+            || (!entry.is_empty() && (host == entry || host.ends_with(&format!(".{}", entry))))
+    })
+}
+
+/// The proxy to send a request to `url` through, from `HTTPS_PROXY`,
+/// `HTTP_PROXY` or `ALL_PROXY`, unless `NO_PROXY` excludes its host.
+pub fn proxy_for(url: &Url) -> Result<Option<Url>, RequestError> {
+    let proxy = match url.scheme() {
+        "https" => var(&["https_proxy", "HTTPS_PROXY", "all_proxy", "ALL_PROXY"]),
+        _ => var(&["http_proxy", "HTTP_PROXY", "all_proxy", "ALL_PROXY"]),
+    };
+    let proxy = match proxy {
+        Some(ref proxy) if !bypass(url.host_str().unwrap_or("")) => proxy,
+        _ => return Ok(None),
+    };
+
+    // proxies are often given without a scheme
+    let parsed = if proxy.contains("://") {
+        Url::parse(proxy)
+    } else {
+        Url::parse(&format!("http://{}", proxy))
+    };
+    parsed
+        .map(Some)
+        .map_err(|_| RequestError::Config(format!("Invalid proxy URL: {}", proxy)))
+}
@@ -0,0 +1,116 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use openssl::pkcs12::Pkcs12;
+use openssl::pkey::PKey;
+use openssl::stack::Stack;
+use openssl::x509::X509;
+use reqwest::{Certificate, ClientBuilder, Identity};
+
+use super::RequestError;
+
+/// Certificates to use for TLS, beyond the system's trusted roots.
+#[derive(Clone, Default)]
+pub struct TlsOptions {
+    /// PEM file of extra CA certificates to trust, such as for a TLS inspecting proxy.
+    pub ca_bundle: Option<PathBuf>,
+    /// PEM files of a client certificate and its private key, for servers that require one.
+    pub client_cert: Option<PathBuf>,
+    pub client_key: Option<PathBuf>,
+    /// Don't check that the server's certificate is for its hostname. The
+    /// certificate must still be trusted: this version of reqwest can't turn
+    /// that off.
+    pub skip_hostname_verification: bool,
+}
+
+fn read(path: &Path, what: &str) -> Result<Vec<u8>, RequestError> {
+    fs::read(path).map_err(|err| {
+        RequestError::Config(format!(
+            "Could not read {} {}: {}",
+            what,
+            path.display(),
+            err
+        ))
+    })
+}
+
+fn invalid(path: &Path, what: &str) -> RequestError {
+    RequestError::Config(format!("Invalid {} {}.", what, path.display()))
+}
+
+/// Trusts every certificate in a PEM bundle, as well as the system's.
+fn add_ca_bundle(client: &mut ClientBuilder, path: &Path) -> Result<(), RequestError> {
+    let certs =
+        X509::stack_from_pem(&read(path, "CA bundle")?).map_err(|_| invalid(path, "CA bundle"))?;
+    if certs.is_empty() {
+        return Err(invalid(path, "CA bundle"));
+    }
+
+    for cert in certs {
+        let der = cert.to_der().map_err(|_| invalid(path, "CA bundle"))?;
+        client.add_root_certificate(
+            Certificate::from_der(&der).map_err(|_| invalid(path, "CA bundle"))?,
+        );
+    }
+    Ok(())
+}
+
+/// This version of reqwest only takes client certificates as PKCS #12, so
+/// the PEM certificate and key are bundled up first. Any certificates after
+/// the first in the certificate file are sent as its chain.
+fn identity(cert_path: &Path, key_path: &Path) -> Result<Identity, RequestError> {
+    let bad_cert = || invalid(cert_path, "client certificate");
+    let mut certs =
+        X509::stack_from_pem(&read(cert_path, "client certificate")?).map_err(|_| bad_cert())?;
+    if certs.is_empty() {
+        return Err(bad_cert());
+    }
+    let cert = certs.remove(0);
+    let key = PKey::private_key_from_pem(&read(key_path, "client key")?)
+        .map_err(|_| invalid(key_path, "client key"))?;
+
+    let mut builder = Pkcs12::builder();
+    if !certs.is_empty() {
+        let mut chain = Stack::new().map_err(|_| bad_cert())?;
+        for cert in certs {
+            chain.push(cert).map_err(|_| bad_cert())?;
+        }
+        builder.ca(chain);
+    }
+    let der = builder
+        .build("", "deployc", &key, &cert)
+        .and_then(|pkcs12| pkcs12.to_der())
+        .map_err(|_| {
+            RequestError::Config(format!(
+                "Client key {} does not match certificate {}.",
+                key_path.display(),
+                cert_path.display()
+            ))
+        })?;
+    Identity::from_pkcs12_der(&der, "").map_err(|_| bad_cert())
+}
+
+/// Applies the TLS settings to a client.
+pub fn configure(client: &mut ClientBuilder, options: &TlsOptions) -> Result<(), RequestError> {
+    if let Some(ref path) = options.ca_bundle {
+        add_ca_bundle(client, path)?;
+    }
+
+    match (&options.client_cert, &options.client_key) {
+        (Some(cert), Some(key)) => {
+            client.identity(identity(cert, key)?);
+        }
+        (None, None) => {}
+        _ => {
+            return Err(RequestError::Config(
+                "Both clientCert and clientKey must be set to use a client certificate."
+                    .to_string(),
+            ))
+        }
+    }
+
+    if options.skip_hostname_verification {
+        client.danger_disable_hostname_verification();
+    }
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use reqwest::{self, Request, Response};
+use serde_json::Value;
+
+use super::error;
+use super::version::{XAPIDeprecatedVersions, XAPISupportedVersions};
+use super::XAPIVersion;
+
+const REDACTED: &str = "[redacted]";
+// request body fields that hold credentials or secrets
+const SECRET_FIELDS: &[&str] = &[
+    "password",
+    "otp",
+    "token",
+    "refreshToken",
+    "deviceCode",
+    "secret",
+    "value",
+];
+const SECRET_HEADERS: &[&str] = &["authorization", "cookie", "proxy-authorization"];
+
+fn millis(elapsed: Duration) -> u64 {
+    elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_millis())
+}
+
+fn log(prefix: &str, line: &str) {
+    eprintln!("debug: {} {}", prefix, line);
+}
+
+fn redact(body: &Value) -> Value {
+    match body {
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(k, v)| {
+                    let v = if SECRET_FIELDS.contains(&k.as_str()) && !v.is_null() {
+                        Value::String(REDACTED.to_string())
+                    } else {
+                        redact(v)
+                    };
+                    (k.clone(), v)
+                })
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(values.iter().map(redact).collect()),
+        _ => body.clone(),
+    }
+}
+
+/// Logs a request about to be sent, without its credentials.
+pub fn request(req: &Request, body: Option<&Value>) {
+    log(">", &format!("{} {}", req.method(), req.url()));
+    for header in req.headers().iter() {
+        let value = if SECRET_HEADERS.contains(&header.name().to_lowercase().as_str()) {
+            REDACTED.to_string()
+        } else {
+            header.value_string()
+        };
+        log(">", &format!("{}: {}", header.name(), value));
+    }
+    if let Some(body) = body {
+        log(">", &redact(body).to_string());
+    }
+}
+
+pub fn response(res: &Response, elapsed: Duration) {
+    log("<", &format!("{} in {}ms", res.status(), millis(elapsed)));
+    if let Some(version) = res.headers().get::<XAPIVersion>() {
+        log("<", &format!("X-API-Version: {}", version));
+    }
+    if let Some(versions) = res.headers().get::<XAPISupportedVersions>() {
+        log("<", &format!("X-API-Supported-Versions: {}", versions));
+    }
+    if let Some(versions) = res.headers().get::<XAPIDeprecatedVersions>() {
+        log("<", &format!("X-API-Deprecated-Versions: {}", versions));
+    }
+    if let Some(id) = error::request_id(res) {
+        log("<", &format!("X-Request-Id: {}", id));
+    }
+}
+
+pub fn failure(err: &reqwest::Error, elapsed: Duration) {
+    log("<", &format!("failed after {}ms: {}", millis(elapsed), err));
+}
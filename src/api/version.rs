@@ -0,0 +1,56 @@
+use reqwest::Response;
+
+use super::XAPIVersion;
+
+header! { (XAPISupportedVersions, "X-API-Supported-Versions") => (String)* }
+header! { (XAPIDeprecatedVersions, "X-API-Deprecated-Versions") => (String)* }
+
+/// Whether the server still accepts an API version.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compatibility {
+    Supported,
+    /// Still accepted, but due to be removed.
+    Deprecated,
+    Unsupported,
+}
+
+/// The API versions a server accepts, from the headers on its responses.
+/// Versions are dates (`2018-06-01`), so they compare as strings.
+#[derive(Clone, Debug)]
+pub struct ServerVersion {
+    /// The newest version the server speaks.
+    pub current: Option<String>,
+    pub supported: Vec<String>,
+    pub deprecated: Vec<String>,
+}
+
+impl ServerVersion {
+    /// `None` for servers that don't say which versions they support.
+    pub fn from_response(res: &Response) -> Option<ServerVersion> {
+        let headers = res.headers();
+        let supported = headers.get::<XAPISupportedVersions>();
+        let deprecated = headers.get::<XAPIDeprecatedVersions>();
+        if supported.is_none() && deprecated.is_none() {
+            return None;
+        }
+
+        Some(ServerVersion {
+            current: headers
+                .get::<XAPIVersion>()
+                .map(|version| version.0.clone()),
+            supported: supported.map_or_else(Vec::new, |versions| versions.0.clone()),
+            deprecated: deprecated.map_or_else(Vec::new, |versions| versions.0.clone()),
+        })
+    }
+
+    pub fn compatibility(&self, version: &str) -> Compatibility {
+        let listed = |versions: &[String]| versions.iter().any(|v| v == version);
+        if listed(&self.deprecated) {
+            Compatibility::Deprecated
+        } else if self.supported.is_empty() || listed(&self.supported) {
+            Compatibility::Supported
+        } else {
+            Compatibility::Unsupported
+        }
+    }
+}
@@ -36,7 +36,7 @@ impl<'de> Deserialize<'de> for EnvVar {
         #[serde(field_identifier, rename_all = "lowercase")]
         enum Field {
             Secret,
-        };
+        }
 
         impl<'v> Visitor<'v> for ValueVisitor {
             type Value = EnvVar;
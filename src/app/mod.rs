@@ -1,22 +1,45 @@
 mod env_var;
 mod restart_policy;
 mod secret;
+mod secret_mount;
 mod service;
 
 pub use self::env_var::EnvVar;
 pub use self::restart_policy::RestartPolicy;
 pub use self::secret::{Secret, SecretType};
+pub use self::secret_mount::SecretMount;
 pub use self::service::Service;
 
-use std::env;
+use std::fmt;
 use std::fs::File;
-use std::path::PathBuf;
+use std::path::Path;
+use std::{env, io};
 
 use chrono::{DateTime, Utc};
 use serde_json;
 use slug::slugify;
 
-use commands::CommandError;
+/// A problem reading or writing an app's `deployc.json`.
+pub enum AppFileError {
+    /// The file exists but is not a valid app config.
+    Invalid(serde_json::Error),
+    CurrentDir(io::Error),
+    Exists,
+    Create(io::Error),
+    Write(serde_json::Error),
+}
+
+impl fmt::Display for AppFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AppFileError::Invalid(err) => write!(f, "Failed to read config file: {}", err),
+            AppFileError::CurrentDir(_) => write!(f, "Cannot access current directory."),
+            AppFileError::Exists => write!(f, "App config file already exists."),
+            AppFileError::Create(_) => write!(f, "Failed to create config file."),
+            AppFileError::Write(_) => write!(f, "Failed writing config to file."),
+        }
+    }
+}
 
 fn is_zero(u: &u16) -> bool {
     u == &0
@@ -33,6 +56,8 @@ pub struct App {
     pub restart: RestartPolicy,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub services: Vec<Service>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<SecretMount>,
     #[serde(default, skip_serializing_if = "is_zero")]
     pub port: u16,
     #[serde(default, skip_serializing)]
@@ -40,49 +65,45 @@ pub struct App {
 }
 
 impl App {
-    pub fn get(dir: &PathBuf) -> Result<App, CommandError> {
+    pub fn get(dir: &Path) -> Result<App, AppFileError> {
         let filepath = dir.join("deployc.json");
         if filepath.exists() {
             if let Ok(f) = File::open(filepath) {
-                return serde_json::from_reader(f).map_err(|err| {
-                    CommandError::with_message(format!("Failed to read config file: {}", err))
-                });
+                return serde_json::from_reader(f).map_err(AppFileError::Invalid);
             }
         }
 
-        let mut app = App::default();
-        app.name = slugify(dir.file_name().unwrap().to_str().unwrap());
-        Ok(app)
+        Ok(App {
+            name: slugify(dir.file_name().unwrap().to_str().unwrap()),
+            ..App::default()
+        })
     }
 
-    pub fn create(name: &String) -> Result<App, CommandError> {
-        let mut app = App::default();
-        app.name = name.clone();
+    pub fn create(name: &str) -> Result<App, AppFileError> {
+        let app = App {
+            name: name.to_string(),
+            ..App::default()
+        };
         app.create_file(true)?;
         Ok(app)
     }
 
-    fn create_file(&self, fail_if_exists: bool) -> Result<(), CommandError> {
-        let dir = env::current_dir()
-            .map_err(|_| CommandError::with_message("Cannot access current directory."))?;
+    fn create_file(&self, fail_if_exists: bool) -> Result<(), AppFileError> {
+        let dir = env::current_dir().map_err(AppFileError::CurrentDir)?;
 
         let filepath = dir.join("deployc.json");
         if filepath.exists() && fail_if_exists {
-            return Err(CommandError::with_message(
-                "App config file already exists.",
-            ));
+            return Err(AppFileError::Exists);
         }
 
-        let f = File::create(filepath)
-            .map_err(|_| CommandError::with_message("Failed to create config file."))?;
+        let f = File::create(filepath).map_err(AppFileError::Create)?;
 
-        serde_json::to_writer_pretty(f, self)
-            .map_err(|_| CommandError::with_message("Failed writing config to file."))?;
+        serde_json::to_writer_pretty(f, self).map_err(AppFileError::Write)?;
 
         Ok(())
     }
 
-    pub fn store(&self) -> Result<(), CommandError> {
+    pub fn store(&self) -> Result<(), AppFileError> {
         self.create_file(false)
     }
 }
@@ -95,6 +116,7 @@ impl Default for App {
             command: vec![],
             restart: RestartPolicy::default(),
             services: vec![],
+            files: vec![],
             port: 0,
             tier: "".to_string(),
         }
@@ -8,6 +8,9 @@ pub enum SecretType {
     Raw,
     Certificate,
     Credentials,
+    SshKey,
+    Registry,
+    Json,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -25,15 +28,26 @@ impl SecretType {
             SecretType::Raw => "raw",
             SecretType::Certificate => "certificate",
             SecretType::Credentials => "credentials",
+            SecretType::SshKey => "ssh-key",
+            SecretType::Registry => "registry",
+            SecretType::Json => "json",
         }
     }
 
-    pub fn is_raw(&self) -> bool {
+    /// Keys inside the secret's value that can be referenced individually.
+    pub fn keys(&self) -> &[&str] {
         match self {
-            SecretType::Raw => true,
-            _ => false,
+            SecretType::Raw | SecretType::Json => &[],
+            SecretType::Certificate => &["tls.key", "tls.cert"],
+            SecretType::Credentials => &["username", "password"],
+            SecretType::SshKey => &["ssh-privatekey", "ssh-publickey"],
+            SecretType::Registry => &["server", "username", "password"],
         }
     }
+
+    pub fn is_raw(&self) -> bool {
+        matches!(self, SecretType::Raw)
+    }
 }
 
 impl FromStr for SecretType {
@@ -44,8 +58,11 @@ impl FromStr for SecretType {
             "raw" => Ok(SecretType::Raw),
             "certificate" => Ok(SecretType::Certificate),
             "credentials" => Ok(SecretType::Credentials),
+            "ssh-key" => Ok(SecretType::SshKey),
+            "registry" => Ok(SecretType::Registry),
+            "json" => Ok(SecretType::Json),
             _ => Err(format!(
-                "Expected 'raw', 'certificate', or 'credentials'. Found '{}'",
+                "Expected 'raw', 'certificate', 'credentials', 'ssh-key', 'registry', or 'json'. Found '{}'",
                 s
             )),
         }
@@ -67,6 +84,6 @@ impl<'de> Deserialize<'de> for SecretType {
         D: Deserializer<'de>,
     {
         let s = String::deserialize(deserializer)?;
-        SecretType::from_str(&s).map_err(|err| de::Error::custom(err))
+        SecretType::from_str(&s).map_err(de::Error::custom)
     }
 }
@@ -0,0 +1,80 @@
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+/// Unix permission bits of a mounted file, written as an octal string ("0400").
+#[derive(Clone, Copy)]
+pub struct FileMode(pub u32);
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretMount {
+    pub secret: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    pub path: String,
+    #[serde(default)]
+    pub mode: FileMode,
+}
+
+impl Default for FileMode {
+    fn default() -> FileMode {
+        FileMode(0o400)
+    }
+}
+
+impl fmt::Display for FileMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04o}", self.0)
+    }
+}
+
+impl Serialize for FileMode {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&format!("{}", self))
+    }
+}
+
+impl<'de> Deserialize<'de> for FileMode {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct ValueVisitor;
+
+        impl<'v> Visitor<'v> for ValueVisitor {
+            type Value = FileMode;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(formatter, "octal string such as '0400'")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                match u32::from_str_radix(v, 8) {
+                    Ok(m) if m <= 0o777 => Ok(FileMode(m)),
+                    _ => Err(de::Error::custom(format!("Invalid value for mode: {}", v))),
+                }
+            }
+
+            // JSON has no octal numbers, so 400 can't mean what it looks like
+            fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Err(de::Error::custom(format!(
+                    "Invalid value for mode: {}. Use an octal string such as \"0400\".",
+                    v
+                )))
+            }
+        }
+
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
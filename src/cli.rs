@@ -1,10 +1,11 @@
 use std::fmt::Display;
-use std::io::{stdin, stdout, Stdout, Write};
+use std::io::{self, stdin, stdout, ErrorKind, Stdout, Write};
 
 use colored::*;
+use prettytable::format;
 use prettytable::row::Row;
-use prettytable::{format, Table};
 use slug::slugify;
+use term;
 use termion;
 use termion::event::Key;
 use termion::input::TermRead;
@@ -12,7 +13,7 @@ use termion::raw::{IntoRawMode, RawTerminal};
 
 use commands::CommandError;
 
-const POINTER: &'static str = "❯";
+const POINTER: &str = "❯";
 
 pub fn prompt<S>(s: S) -> Option<String>
 where
@@ -34,19 +35,48 @@ pub fn prompt_password<S>(s: S) -> Option<String>
 where
     S: Display,
 {
+    // there's no echo to hide when the password is piped in, as from a script,
+    // but the line ending is no more part of it than when typed
+    if !termion::is_tty(&stdin()) {
+        return prompt(s).map(|mut p| {
+            let len = trim_line_ending(p.as_bytes()).len();
+            p.truncate(len);
+            p
+        });
+    }
+
     let mut stdin = stdin();
     let mut stdout = stdout();
 
     print!("{}", s);
     stdout.flush().unwrap();
     if let Ok(Some(p)) = stdin.read_passwd(&mut stdout) {
-        println!("");
+        println!();
         Some(p)
     } else {
         None
     }
 }
 
+/// Leaves out one trailing `\n` or `\r\n`, such as `echo` adds.
+pub fn trim_line_ending(bytes: &[u8]) -> &[u8] {
+    let bytes = if bytes.ends_with(b"\n") {
+        &bytes[..bytes.len() - 1]
+    } else {
+        return bytes;
+    };
+    if bytes.ends_with(b"\r") {
+        &bytes[..bytes.len() - 1]
+    } else {
+        bytes
+    }
+}
+
+/// Whether the user can be prompted, i.e. stdin and stdout are both terminals.
+pub fn is_interactive() -> bool {
+    termion::is_tty(&stdin()) && termion::is_tty(&stdout())
+}
+
 pub fn prompt_credentials() -> Result<(String, String), CommandError> {
     let username =
         prompt("  Username: ".bold()).ok_or(CommandError::with_message("Invalid username."))?;
@@ -59,20 +89,51 @@ pub fn prompt_credentials() -> Result<(String, String), CommandError> {
     Ok((slugify(username), password))
 }
 
-pub fn print_table(header: Row, rows: Vec<Row>) {
-    let mut table = Table::new();
-    table.set_titles(header);
-    for row in rows {
-        table.add_row(row);
-    }
+pub fn print_table(header: Row, rows: Vec<Row>) -> Result<(), CommandError> {
+    // Prints the rows itself: `Table::printstd` transmutes the table's `Vec`
+    // of rows into a slice, which crashes with newer compilers.
+    let format = *format::consts::FORMAT_CLEAN;
+    let rows: Vec<Row> = Some(header).into_iter().chain(rows).collect();
+    let columns = rows.iter().map(Row::len).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|i| {
+            rows.iter()
+                .map(|row| row.get_cell_width(i))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    // styles only go to terminals, like `Table::printstd`
+    let result = match term::stdout() {
+        Some(ref mut out) if termion::is_tty(&stdout()) => rows
+            .iter()
+            .try_for_each(|row| row.print_term(&mut **out, &format, &widths)),
+        _ => {
+            let mut out = stdout();
+            rows.iter()
+                .try_for_each(|row| row.print(&mut out, &format, &widths))
+        }
+    };
+    output_written(result)
+}
 
-    table.set_format(*format::consts::FORMAT_CLEAN);
-    table.printstd();
+/// Whether writing a command's output to stdout worked.
+pub fn output_written(result: io::Result<()>) -> Result<(), CommandError> {
+    match result {
+        // the reader went away, e.g. `deployc list | head -1`, and has seen all it wants
+        Err(ref err) if err.kind() == ErrorKind::BrokenPipe => Ok(()),
+        Err(err) => Err(CommandError::with_message(format!(
+            "Could not write output: {}",
+            err
+        ))),
+        Ok(()) => Ok(()),
+    }
 }
 
 fn write_options<T>(
     stdout: &mut RawTerminal<Stdout>,
-    options: &Vec<T>,
+    options: &[T],
     choice: usize,
     curr_idx: Option<usize>,
 ) where
@@ -86,7 +147,7 @@ fn write_options<T>(
                 POINTER.cyan().bold(),
                 format!("{}", opt).cyan().bold()
             ).unwrap();
-        } else if curr_idx.map_or(false, |c| c == i) {
+        } else if curr_idx == Some(i) {
             let opt = format!("{}", opt).dimmed().bold();
             write!(stdout, "   {} {}\r\n", opt, "(current)".dimmed()).unwrap();
         } else {
@@ -97,7 +158,7 @@ fn write_options<T>(
     stdout.flush().unwrap();
 }
 
-pub fn get_option<T>(options: &Vec<T>, current_value: Option<T>) -> Result<Option<&T>, CommandError>
+pub fn get_option<T>(options: &[T], current_value: Option<T>) -> Result<Option<&T>, CommandError>
 where
     T: Display,
     T: PartialEq,
@@ -113,7 +174,7 @@ where
         termion::cursor::Hide
     ).unwrap();
 
-    let curr_idx = current_value.map_or(None, |opt| options.iter().position(|o| o == &opt));
+    let curr_idx = current_value.and_then(|opt| options.iter().position(|o| o == &opt));
     let mut choice = if curr_idx.is_some() && curr_idx.unwrap() == 0 {
         1
     } else {
@@ -121,7 +182,7 @@ where
     };
     let mut canceled = false;
 
-    write_options(&mut stdout, &options, choice, curr_idx);
+    write_options(&mut stdout, options, choice, curr_idx);
     for c in stdin.keys() {
         write!(
             stdout,
@@ -136,16 +197,16 @@ where
                 break;
             }
             Key::Up if choice > 0 => {
-                if curr_idx.map_or(false, |i| i == choice - 1 && i != 0) {
+                if curr_idx == Some(choice - 1) && choice - 1 != 0 {
                     choice -= 2;
-                } else if curr_idx.map_or(true, |i| i != choice - 1) {
+                } else if curr_idx != Some(choice - 1) {
                     choice -= 1;
                 }
             }
             Key::Down if choice < options.len() - 1 => {
-                if curr_idx.map_or(false, |i| i == choice + 1 && i != options.len() - 1) {
+                if curr_idx == Some(choice + 1) && choice + 1 != options.len() - 1 {
                     choice += 2;
-                } else if curr_idx.map_or(true, |i| i != choice + 1) {
+                } else if curr_idx != Some(choice + 1) {
                     choice += 1;
                 }
             }
@@ -161,7 +222,7 @@ where
             _ => {}
         }
 
-        write_options(&mut stdout, &options, choice, curr_idx);
+        write_options(&mut stdout, options, choice, curr_idx);
     }
 
     write!(stdout, "{}", termion::cursor::Show).unwrap();
@@ -173,10 +234,10 @@ where
     Err(CommandError::with_message("Could not choose option."))
 }
 
-pub fn ellipsis(s: &String, max_len: usize) -> String {
+pub fn ellipsis(s: &str, max_len: usize) -> String {
     if s.len() > max_len {
         format!("{}…", s.chars().take(max_len).collect::<String>())
     } else {
-        s.clone()
+        s.to_string()
     }
 }
@@ -1,18 +1,11 @@
 use colored::*;
+use deployc::api::ApiClient;
 
-use api::API;
 use commands::CommandError;
-use config::Config;
 
-#[derive(Serialize, Deserialize)]
-struct HasCardResponse {
-    exists: bool,
-}
-
-pub fn check_card(config: &Config) -> Result<(), CommandError> {
+pub fn check_card(client: &ApiClient) -> Result<(), CommandError> {
     // check that user has a valid credit card
-    let HasCardResponse { exists } = API::new(config).has_card().get()?;
-    if !exists {
+    if !client.has_card()? {
         return Err(CommandError::with_message_and_help(
             "Missing payment method.",
             &format!(
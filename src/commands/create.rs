@@ -1,19 +1,13 @@
 use clap::ArgMatches;
 use colored::*;
+use deployc::api::ApiClient;
+use deployc::app::App;
+use deployc::models::CreatedApp;
 use slug::slugify;
 use std::env;
 
-use api::API;
-use app::App;
 use commands::common::check_card;
 use commands::CommandError;
-use config::Config;
-
-#[derive(Serialize, Deserialize)]
-struct CreateResponse {
-    name: String,
-    tier: String,
-}
 
 pub struct CreateCommand;
 
@@ -35,14 +29,13 @@ impl CreateCommand {
         Ok(slugify(name))
     }
 
-    pub fn run(matches: &ArgMatches, config: &Config) -> Result<(), CommandError> {
-        check_card(config)?;
+    pub fn run(matches: &ArgMatches, client: &ApiClient) -> Result<(), CommandError> {
+        check_card(client)?;
 
         let name = CreateCommand::get_name(matches)?;
-        let body = json!({ "name": name });
 
         // make request to deployc.io
-        let CreateResponse { tier, .. } = API::new(config).apps().post(&body)?;
+        let CreatedApp { tier, .. } = client.create_app(&name)?;
         println!("{} {}!", "Created app".green().bold(), name.bold());
         println!(
             "Your pricing tier is {}. Run {} to view your current tier or {} upgrade.",
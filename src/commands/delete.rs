@@ -1,21 +1,15 @@
 use clap::ArgMatches;
 use colored::*;
+use deployc::api::ApiClient;
+use deployc::app::App;
 
-use api::API;
-use app::App;
 use cli::prompt;
 use commands::CommandError;
-use config::Config;
 
 pub struct DeleteCommand;
 
-#[derive(Serialize, Deserialize)]
-struct DeleteResponse {
-    message: String,
-}
-
 impl DeleteCommand {
-    pub fn run(matches: &ArgMatches, config: &Config, app: &App) -> Result<(), CommandError> {
+    pub fn run(matches: &ArgMatches, client: &ApiClient, app: &App) -> Result<(), CommandError> {
         if !matches.is_present("force") {
             println!(
                 "{}",
@@ -27,12 +21,12 @@ impl DeleteCommand {
                 "Type the name of the app to confirm {}: ",
                 format!("({})", app.name).dimmed().bold()
             )) {
-                Some(ref app_name) if app_name.trim() == &app.name => println!("Deleting app..."),
+                Some(ref app_name) if app_name.trim() == app.name => println!("Deleting app..."),
                 _ => return Err(CommandError::with_message("Not deleting app.")),
             }
         }
 
-        let DeleteResponse { .. } = API::new(config).app(&app.name).delete()?;
+        client.delete_app(&app.name)?;
         println!("App deleted.");
         Ok(())
     }
@@ -1,16 +1,77 @@
-use serde_json;
+use chrono::Local;
+use clap::ArgMatches;
+use deployc::app::{App, RestartPolicy};
 
-use app::App;
 use commands::CommandError;
+use output::{self, Output, View};
+
+fn restart(policy: &RestartPolicy) -> String {
+    match policy {
+        RestartPolicy::Never => "never".to_string(),
+        RestartPolicy::Always => "always".to_string(),
+        RestartPolicy::OnFailure(times) => format!("on failure, up to {} times", times),
+    }
+}
+
+fn or_none(s: String) -> String {
+    if s.is_empty() {
+        "-".to_string()
+    } else {
+        s
+    }
+}
+
+impl View for App {
+    fn titles(wide: bool) -> Vec<&'static str> {
+        let mut titles = vec!["Name", "Created", "Command", "Restart", "Port"];
+        if wide {
+            titles.extend(&["Services", "Files"]);
+        }
+        titles
+    }
+
+    fn cells(&self, wide: bool) -> Vec<String> {
+        let mut cells = vec![
+            self.name.clone(),
+            self.created_at.with_timezone(&Local).to_rfc3339(),
+            or_none(self.command.join(" ")),
+            restart(&self.restart),
+            or_none(if self.port == 0 {
+                "".to_string()
+            } else {
+                self.port.to_string()
+            }),
+        ];
+        if wide {
+            let services: Vec<_> = self
+                .services
+                .iter()
+                .map(|service| service.context.clone())
+                .collect();
+            let files: Vec<_> = self
+                .files
+                .iter()
+                .map(|file| match file.key {
+                    Some(ref key) => format!("{} ({}/{})", file.path, file.secret, key),
+                    None => format!("{} ({})", file.path, file.secret),
+                })
+                .collect();
+            cells.push(or_none(services.join(", ")));
+            cells.push(or_none(files.join(", ")));
+        }
+        cells
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
 
 pub struct DescribeCommand;
 
 impl DescribeCommand {
-    pub fn run(app: &App) -> Result<(), CommandError> {
-        let s = serde_json::to_string_pretty(&app).map_err(|err| {
-            CommandError::with_message(format!("Could not read app config: {}", err))
-        })?;
-        println!("{}", s);
-        Ok(())
+    pub fn run(matches: &ArgMatches, app: &App) -> Result<(), CommandError> {
+        // the app config has always been shown as JSON
+        output::print_one(Output::from_matches(matches, Output::Json)?, app)
     }
 }
@@ -1,54 +1,60 @@
-use std::collections::HashMap;
-
 use clap::ArgMatches;
 use colored::*;
+use deployc::api::ApiClient;
+use deployc::app::{App, EnvVar};
 
-use api::API;
-use app::{App, EnvVar};
-use cli::print_table;
 use commands::CommandError;
-use config::Config;
+use output::{self, Output, View};
 
-pub struct EnvCommand;
+/// An environment variable, with its value as the API gives it: a string,
+/// or `{"secret": name}`.
+#[derive(Serialize)]
+struct EnvVarView {
+    name: String,
+    value: EnvVar,
+}
+
+impl View for EnvVarView {
+    fn titles(_wide: bool) -> Vec<&'static str> {
+        vec!["Name", "Value"]
+    }
 
-#[derive(Serialize, Deserialize)]
-struct EnvResponse {
-    env: HashMap<String, EnvVar>,
+    fn cells(&self, _wide: bool) -> Vec<String> {
+        let value = match self.value {
+            EnvVar::Value(ref val) => val.clone(),
+            EnvVar::Secret(ref s) => format!("secret: {}", s),
+        };
+        vec![self.name.clone(), value]
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
 }
 
+pub struct EnvCommand;
+
 impl EnvCommand {
-    fn print_env(env: &HashMap<String, EnvVar>) {
-        print_table(
-            row![Fbb => "Name", "Value"],
-            env.iter()
-                .map(|(k, v)| {
-                    row![
-                        k,
-                        match v {
-                            EnvVar::Value(val) => format!("{}", val),
-                            EnvVar::Secret(s) => format!("secret: {}", s),
-                        }
-                    ]
-                })
-                .collect(),
-        );
-    }
+    fn list(matches: &ArgMatches, client: &ApiClient, app: &App) -> Result<(), CommandError> {
+        let output = Output::from_matches(matches, Output::Table)?;
 
-    fn list(config: &Config, app: &App) -> Result<(), CommandError> {
-        let EnvResponse { env } = API::new(config).app(&app.name).param("env").get()?;
-        if env.is_empty() {
-            println!(
+        let mut env: Vec<_> = client
+            .env(&app.name)?
+            .into_iter()
+            .map(|(name, value)| EnvVarView { name, value })
+            .collect();
+        env.sort_by(|v1, v2| v1.name.cmp(&v2.name));
+        output::print_list(
+            output,
+            &env,
+            &format!(
                 "No environment variables. Create one using {}.",
                 "deployc env set".blue().bold()
-            );
-            return Ok(());
-        }
-
-        EnvCommand::print_env(&env);
-        Ok(())
+            ),
+        )
     }
 
-    fn set(matches: &ArgMatches, config: &Config, app: &App) -> Result<(), CommandError> {
+    fn set(matches: &ArgMatches, client: &ApiClient, app: &App) -> Result<(), CommandError> {
         let from_secret = matches.is_present("secret");
         let key = value_t!(matches, "key", String).unwrap();
         let value = value_t!(matches, "value", String).unwrap();
@@ -58,8 +64,7 @@ impl EnvCommand {
             EnvVar::Value(value.clone())
         };
 
-        let body = json!({ "key": key, "value": var });
-        let EnvResponse { .. } = API::new(config).app(&app.name).param("env").post(&body)?;
+        client.set_env(&app.name, &key, &var)?;
 
         let redeploy_text = format!(
             "Run {} to re-deploy with new environment.",
@@ -83,10 +88,11 @@ impl EnvCommand {
         Ok(())
     }
 
-    pub fn run(matches: &ArgMatches, config: &Config, app: &App) -> Result<(), CommandError> {
+    pub fn run(matches: &ArgMatches, client: &ApiClient, app: &App) -> Result<(), CommandError> {
         match matches.subcommand() {
-            ("set", Some(m)) => EnvCommand::set(m, config, app),
-            ("list", _) | _ => EnvCommand::list(config, app),
+            ("set", Some(m)) => EnvCommand::set(m, client, app),
+            ("list", Some(m)) => EnvCommand::list(m, client, app),
+            _ => EnvCommand::list(matches, client, app),
         }
     }
 }
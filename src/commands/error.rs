@@ -1,24 +1,134 @@
 use std::convert::From;
+use std::error;
+use std::fmt;
 
-use reqwest;
+use colored::*;
+use deployc::api::RequestError;
+use deployc::app::AppFileError;
+use reqwest::{self, StatusCode};
 
+// Exit codes, following sysexits.h where there is a match
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_VALIDATION: i32 = 65;
+pub const EXIT_NOT_FOUND: i32 = 66;
+pub const EXIT_NETWORK: i32 = 69;
+pub const EXIT_SERVER: i32 = 70;
+pub const EXIT_RATE_LIMITED: i32 = 75;
+pub const EXIT_PARSE: i32 = 76;
+pub const EXIT_AUTH: i32 = 77;
+pub const EXIT_CONFIG: i32 = 78;
+// past the end of sysexits.h, which has nothing for "upgrade this program"
+pub const EXIT_UNSUPPORTED_VERSION: i32 = 79;
+
+#[derive(Debug)]
 pub struct CommandError {
     pub message: String,
-    pub help: String
+    pub help: String,
+    pub code: i32,
+    /// The id of the API request that failed, for deployc support.
+    pub request_id: Option<String>,
 }
 
 impl CommandError {
     pub fn with_message<S>(msg: S) -> CommandError where S: Into<String> {
-        CommandError { message: msg.into(), help: "".to_string() }
+        CommandError { message: msg.into(), help: "".to_string(), code: EXIT_FAILURE, request_id: None }
     }
 
     pub fn with_message_and_help<S>(msg: S, help: S) -> CommandError where S: Into<String> {
-        CommandError { message: msg.into(), help: help.into() }
+        CommandError { message: msg.into(), help: help.into(), code: EXIT_FAILURE, request_id: None }
+    }
+
+    pub fn with_code(self, code: i32) -> CommandError {
+        CommandError { code, ..self }
+    }
+
+    pub fn with_request_id(self, request_id: Option<String>) -> CommandError {
+        CommandError { request_id, ..self }
     }
 }
 
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+// so it can be passed through the API client, see `Config::client`
+impl error::Error for CommandError {}
+
 impl From<reqwest::Error> for CommandError {
     fn from(err: reqwest::Error) -> CommandError {
+        CommandError::with_message(format!("{}", err)).with_code(EXIT_NETWORK)
+    }
+}
+
+impl From<AppFileError> for CommandError {
+    fn from(err: AppFileError) -> CommandError {
         CommandError::with_message(format!("{}", err))
     }
 }
+
+fn exit_code(err: &RequestError) -> i32 {
+    match err {
+        RequestError::Network(_) => EXIT_NETWORK,
+        RequestError::Config(_) => EXIT_CONFIG,
+        RequestError::Auth(_) | RequestError::Renew(_) => EXIT_AUTH,
+        RequestError::NotFound(_) => EXIT_NOT_FOUND,
+        RequestError::Validation(_) => EXIT_VALIDATION,
+        RequestError::RateLimited { .. } => EXIT_RATE_LIMITED,
+        RequestError::Server(_) => EXIT_SERVER,
+        RequestError::Client(_) | RequestError::Body(_) => EXIT_FAILURE,
+        RequestError::UnsupportedVersion { .. } => EXIT_UNSUPPORTED_VERSION,
+        RequestError::Parse { .. } => EXIT_PARSE,
+    }
+}
+
+impl From<RequestError> for CommandError {
+    fn from(err: RequestError) -> CommandError {
+        // renewing the session failed with an error of our own
+        let err = match err {
+            RequestError::Renew(err) => match err.downcast::<CommandError>() {
+                Ok(err) => return *err,
+                Err(err) => RequestError::Renew(err),
+            },
+            err => err,
+        };
+
+        let help = match &err {
+            RequestError::RateLimited {
+                retry_after_secs: Some(secs),
+                ..
+            } => format!("Try again in {} seconds.", secs),
+            RequestError::Auth(ref response) if response.status == StatusCode::Unauthorized => {
+                format!("{} {}", "Run".dimmed(), "deployc login".bold())
+            }
+            RequestError::UnsupportedVersion { .. } => {
+                "Upgrade deployc to the latest version.".to_string()
+            }
+            RequestError::Server(_) | RequestError::Parse { .. } => {
+                "If this keeps happening, contact deployc support with the request ID.".to_string()
+            }
+            _ => "".to_string(),
+        };
+
+        // the same as the error's Display, with the field names highlighted
+        let message = match err.response() {
+            Some(response) => {
+                let mut message = response.error.error.clone();
+                for field in &response.error.fields {
+                    message += &format!(
+                        "\n  {} {}",
+                        format!("{}:", field.field).bold(),
+                        field.message
+                    );
+                }
+                message
+            }
+            None => format!("{}", err),
+        };
+
+        CommandError::with_message_and_help(message, help)
+            .with_code(exit_code(&err))
+            .with_request_id(err.request_id().map(|id| id.to_string()))
+    }
+}
@@ -1,7 +1,7 @@
 use clap::ArgMatches;
 use colored::*;
+use deployc::app::App;
 
-use app::App;
 use commands::CommandError;
 
 pub struct ExposeCommand;
@@ -0,0 +1,213 @@
+use std::str::FromStr;
+
+use base64;
+use rand::rngs::OsRng;
+use rand::{Rng, RngCore};
+
+use commands::CommandError;
+
+const ALNUM: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+const HEX: &str = "0123456789abcdef";
+const BASE64: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64URL: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+pub enum Charset {
+    Alnum,
+    Hex,
+    Base64,
+    Base64Url,
+    AsciiPrintable,
+    Custom(Vec<char>),
+}
+
+/// How much to generate: a number of characters drawn from the charset, or a
+/// number of random bytes encoded with it.
+pub enum Size {
+    Length(usize),
+    Bytes(usize),
+}
+
+pub struct Generated {
+    pub value: String,
+    pub entropy_bits: f64,
+}
+
+impl Charset {
+    fn chars(&self) -> Vec<char> {
+        match self {
+            Charset::Alnum => ALNUM.chars().collect(),
+            Charset::Hex => HEX.chars().collect(),
+            Charset::Base64 => BASE64.chars().collect(),
+            Charset::Base64Url => BASE64URL.chars().collect(),
+            Charset::AsciiPrintable => (b'!'..b'~' + 1).map(|b| b as char).collect(),
+            Charset::Custom(chars) => chars.clone(),
+        }
+    }
+
+    /// Encodes raw bytes directly, for charsets that are byte encodings.
+    fn encode(&self, bytes: &[u8]) -> Option<String> {
+        match self {
+            Charset::Hex => Some(bytes.iter().map(|b| format!("{:02x}", b)).collect()),
+            Charset::Base64 => Some(base64::encode(bytes)),
+            Charset::Base64Url => Some(base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Charset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "alnum" => Ok(Charset::Alnum),
+            "hex" => Ok(Charset::Hex),
+            "base64" => Ok(Charset::Base64),
+            "base64url" => Ok(Charset::Base64Url),
+            "ascii-printable" => Ok(Charset::AsciiPrintable),
+            _ if s.starts_with("custom:") => {
+                let mut chars: Vec<char> = s["custom:".len()..].chars().collect();
+                chars.sort();
+                chars.dedup();
+                if chars.len() < 2 {
+                    Err("Custom charset must contain at least 2 distinct characters.".to_string())
+                } else {
+                    Ok(Charset::Custom(chars))
+                }
+            }
+            _ => Err(format!(
+                "Expected 'alnum', 'hex', 'base64', 'base64url', 'ascii-printable', or 'custom:<chars>'. Found '{}'",
+                s
+            )),
+        }
+    }
+}
+
+pub(crate) fn os_rng() -> Result<OsRng, CommandError> {
+    OsRng::new().map_err(|err| {
+        CommandError::with_message(format!(
+            "Could not access OS random number generator: {}",
+            err
+        ))
+    })
+}
+
+fn sample(rng: &mut OsRng, chars: &[char], length: usize) -> String {
+    (0..length)
+        .map(|_| chars[rng.gen_range(0, chars.len())])
+        .collect()
+}
+
+pub fn generate(charset: &Charset, size: Size) -> Result<Generated, CommandError> {
+    let mut rng = os_rng()?;
+    let chars = charset.chars();
+    let bits_per_char = (chars.len() as f64).log2();
+
+    let generated = match size {
+        Size::Length(length) => Generated {
+            value: sample(&mut rng, &chars, length),
+            entropy_bits: length as f64 * bits_per_char,
+        },
+        Size::Bytes(num_bytes) => {
+            let mut bytes = vec![0u8; num_bytes];
+            rng.fill_bytes(&mut bytes);
+            match charset.encode(&bytes) {
+                Some(value) => Generated {
+                    value,
+                    entropy_bits: num_bytes as f64 * 8.0,
+                },
+                None => {
+                    // not a byte encoding, so draw enough characters to cover the requested entropy
+                    let length = (num_bytes as f64 * 8.0 / bits_per_char).ceil() as usize;
+                    Generated {
+                        value: sample(&mut rng, &chars, length),
+                        entropy_bits: length as f64 * bits_per_char,
+                    }
+                }
+            }
+        }
+    };
+
+    Ok(generated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn charset(s: &str) -> Charset {
+        s.parse().unwrap()
+    }
+
+    fn drawn_from(value: &str, charset: &Charset) -> bool {
+        let chars = charset.chars();
+        value.chars().all(|c| chars.contains(&c))
+    }
+
+    #[test]
+    fn charset_contents() {
+        assert_eq!(charset("alnum").chars().len(), 62);
+        assert_eq!(charset("hex").chars().len(), 16);
+        assert_eq!(charset("base64").chars().len(), 64);
+        assert_eq!(charset("base64url").chars().len(), 64);
+        assert!(charset("base64url").chars().contains(&'-'));
+        assert!(!charset("base64url").chars().contains(&'+'));
+
+        let printable = charset("ascii-printable").chars();
+        assert_eq!(printable.len(), 94);
+        assert_eq!((printable[0], printable[93]), ('!', '~'));
+
+        assert_eq!(charset("custom:abca").chars(), vec!['a', 'b', 'c']);
+        assert!("custom:aa".parse::<Charset>().is_err());
+        assert!("latin1".parse::<Charset>().is_err());
+    }
+
+    #[test]
+    fn length_counts_characters() {
+        for name in &["alnum", "hex", "base64", "ascii-printable", "custom:ab"] {
+            let charset = charset(name);
+            let generated = generate(&charset, Size::Length(20)).unwrap();
+            assert_eq!(generated.value.chars().count(), 20, "{}", name);
+            assert!(drawn_from(&generated.value, &charset), "{}", name);
+        }
+    }
+
+    #[test]
+    fn bytes_are_encoded() {
+        let generated = generate(&charset("hex"), Size::Bytes(16)).unwrap();
+        assert_eq!(generated.value.len(), 32);
+        assert_eq!(generated.entropy_bits, 128.0);
+
+        let generated = generate(&charset("base64"), Size::Bytes(16)).unwrap();
+        assert_eq!(base64::decode(&generated.value).unwrap().len(), 16);
+        assert_eq!(generated.entropy_bits, 128.0);
+
+        let generated = generate(&charset("base64url"), Size::Bytes(16)).unwrap();
+        assert_eq!(generated.value.len(), 22);
+        assert!(drawn_from(&generated.value, &charset("base64url")));
+    }
+
+    #[test]
+    fn bytes_cover_entropy_in_other_charsets() {
+        // 128 bits at log2(62) ≈ 5.95 bits per character
+        let generated = generate(&charset("alnum"), Size::Bytes(16)).unwrap();
+        assert_eq!(generated.value.len(), 22);
+        assert!(generated.entropy_bits >= 128.0);
+
+        let generated = generate(&charset("custom:01"), Size::Bytes(2)).unwrap();
+        assert_eq!(generated.value.len(), 16);
+        assert_eq!(generated.entropy_bits, 16.0);
+    }
+
+    #[test]
+    fn entropy_per_character() {
+        let generated = generate(&charset("hex"), Size::Length(10)).unwrap();
+        assert_eq!(generated.entropy_bits, 40.0);
+        let generated = generate(&charset("custom:abc"), Size::Length(4)).unwrap();
+        assert!((generated.entropy_bits - 4.0 * 3f64.log2()).abs() < 1e-9);
+        assert_eq!(
+            generate(&charset("alnum"), Size::Length(0)).unwrap().value,
+            ""
+        );
+    }
+}
@@ -1,43 +1,76 @@
-use chrono::Local;
+use std::cmp::Reverse;
+
+use chrono::{DateTime, Local, Utc};
 use chrono_humanize::HumanTime;
+use clap::ArgMatches;
 use colored::*;
+use deployc::api::ApiClient;
 
-use api::API;
-use app::App;
-use cli::{ellipsis, print_table};
+use cli::ellipsis;
 use commands::CommandError;
-use config::Config;
+use output::{self, Output, View};
 
-#[derive(Serialize, Deserialize)]
-struct ListResponse {
-    apps: Vec<App>,
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AppView {
+    name: String,
+    tier: String,
+    created_at: DateTime<Utc>,
+}
+
+impl View for AppView {
+    fn titles(wide: bool) -> Vec<&'static str> {
+        if wide {
+            vec!["Name", "Tier", "Created", "Created At"]
+        } else {
+            vec!["Name", "Tier", "Created"]
+        }
+    }
+
+    fn cells(&self, wide: bool) -> Vec<String> {
+        let created_at = self.created_at.with_timezone(&Local);
+        let mut cells = vec![
+            if wide {
+                self.name.clone()
+            } else {
+                ellipsis(&self.name, 18)
+            },
+            self.tier.clone(),
+            HumanTime::from(created_at).to_string(),
+        ];
+        if wide {
+            cells.push(created_at.to_rfc3339());
+        }
+        cells
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
 }
 
 pub struct ListCommand;
 
 impl ListCommand {
-    pub fn run(config: &Config) -> Result<(), CommandError> {
-        // make request to deployc.io
-        let ListResponse { mut apps } = API::new(config).apps().get()?;
+    pub fn run(matches: &ArgMatches, client: &ApiClient) -> Result<(), CommandError> {
+        let output = Output::from_matches(matches, Output::Table)?;
 
-        if apps.len() == 0 {
-            println!("No apps! Use {} to create an app.", "deployc create".bold());
-        } else {
-            apps.sort_by(|a1, a2| a2.created_at.cmp(&a1.created_at));
-            print_table(
-                row![Fbb => "Name", "Tier", "Created"],
-                apps.iter()
-                    .map(|app| {
-                        row![
-                            ellipsis(&app.name, 18),
-                            app.tier,
-                            HumanTime::from(app.created_at.with_timezone(&Local))
-                        ]
-                    })
-                    .collect(),
-            );
-        }
+        // make request to deployc.io
+        let mut apps = client.apps()?;
+        apps.sort_by_key(|a| Reverse(a.created_at));
+        let apps: Vec<_> = apps
+            .into_iter()
+            .map(|app| AppView {
+                name: app.name,
+                tier: app.tier,
+                created_at: app.created_at,
+            })
+            .collect();
 
-        Ok(())
+        output::print_list(
+            output,
+            &apps,
+            &format!("No apps! Use {} to create an app.", "deployc create".bold()),
+        )
     }
 }
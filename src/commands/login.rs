@@ -1,33 +1,209 @@
+use std::io::{self, Read};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use clap::ArgMatches;
 use colored::*;
+use deployc::api::{ApiClient, RequestError};
+use deployc::models::{DeviceCode, TokenResponse};
 
-use api::API;
-use cli::prompt_credentials;
+use cli::{is_interactive, prompt, prompt_credentials};
 use commands::CommandError;
-use config::Config;
-use token_response::TokenResponse;
+use config::{Config, TokenType};
+use version;
+
+/// Error code the server replies with when a second factor is needed.
+const OTP_REQUIRED: &str = "otp_required";
+// polling intervals from RFC 8628
+const DEFAULT_POLL_INTERVAL_SECS: u64 = 5;
+const SLOW_DOWN_SECS: u64 = 5;
 
 pub struct LoginCommand;
 
 impl LoginCommand {
-    pub fn run(config: &mut Config) -> Result<(), CommandError> {
+    fn login_with_token(config: &mut Config) -> Result<(), CommandError> {
+        let mut token = String::new();
+        io::stdin().read_to_string(&mut token).map_err(|err| {
+            CommandError::with_message(format!("Cannot read from stdin: {}", err))
+        })?;
+        let token = token.trim();
+        if token.is_empty() {
+            return Err(CommandError::with_message("No token given on stdin."));
+        }
+
+        config.token = token.to_string();
+        config.token_type = TokenType::Api;
+        config.token_issued_at = None;
+        config.token_expires_at = None;
+        config.token_from_env = false;
+
+        // make sure the token is valid before storing it
+        let client = config.client();
+        let name = client.current_token();
+        version::warn_if_deprecated(&client);
+        let name = match name {
+            Ok(name) => name,
+            Err(err) => {
+                config.token = "".to_string();
+                return Err(err.into());
+            }
+        };
+        println!(
+            "{} {}",
+            "Logged in!".green().bold(),
+            format!("Using API token {}.", name).dimmed()
+        );
+        Ok(())
+    }
+
+    fn prompt_otp() -> Result<String, CommandError> {
+        if !is_interactive() {
+            return Err(CommandError::with_message_and_help(
+                "Two-factor code required.".to_string(),
+                format!("Use {}.", "--otp <code>".bold()),
+            ));
+        }
+
+        let otp = prompt("      Code: ".bold())
+            .ok_or_else(|| CommandError::with_message("Invalid code."))?;
+        Ok(otp.trim().to_string())
+    }
+
+    fn login_with_password(
+        matches: &ArgMatches,
+        client: &ApiClient,
+    ) -> Result<TokenResponse, CommandError> {
         // get username and password
         println!("{}", "Log in to deployc.io".blue().bold());
         let (username, password) = prompt_credentials()?;
 
-        let body = json!({
-            "username": username,
-            "password": password
-        });
-
         // make request to deployc.io
+        LoginCommand::login(client, &username, &password, matches.value_of("otp"))
+    }
+
+    /// Logs in with a username and password, and asks for a two-factor code
+    /// if the account needs one that `otp` doesn't give.
+    pub fn login(
+        client: &ApiClient,
+        username: &str,
+        password: &str,
+        otp: Option<&str>,
+    ) -> Result<TokenResponse, CommandError> {
+        let err = match client.login(username, password, otp) {
+            Ok(token) => return Ok(token),
+            Err(err) => err,
+        };
+        if err.code() != Some(OTP_REQUIRED) || otp.is_some() {
+            return Err(err.into());
+        }
+
+        // second factor required, so ask for it and try again
+        let otp = LoginCommand::prompt_otp()?;
+        Ok(client.login(username, password, Some(&otp))?)
+    }
+
+    fn open_browser(url: &str) -> bool {
+        let opener = if cfg!(target_os = "macos") {
+            "open"
+        } else if cfg!(windows) {
+            "explorer"
+        } else {
+            "xdg-open"
+        };
+        Command::new(opener)
+            .arg(url)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .is_ok()
+    }
+
+    /// Logs in with the OAuth device authorization flow: the user approves the
+    /// login in a browser, which also takes care of SSO and second factors.
+    fn login_with_web(
+        matches: &ArgMatches,
+        client: &ApiClient,
+    ) -> Result<TokenResponse, CommandError> {
+        let DeviceCode {
+            device_code,
+            user_code,
+            verification_uri,
+            verification_uri_complete,
+            expires_in,
+            interval,
+        } = match client.device_code() {
+            Ok(code) => code,
+            // servers from before browser login don't have the endpoint
+            Err(RequestError::NotFound(_)) => {
+                return Err(CommandError::with_message_and_help(
+                    "This server does not support logging in with a browser.".to_string(),
+                    format!("Log in with a password, without {}.", "--web".bold()),
+                ))
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        println!("{}", "Log in to deployc.io in your browser".blue().bold());
+        println!(
+            "  Open {} and enter the code {}",
+            verification_uri.bold(),
+            user_code.cyan().bold()
+        );
+        if !matches.is_present("no-browser") {
+            LoginCommand::open_browser(
+                verification_uri_complete
+                    .as_ref()
+                    .unwrap_or(&verification_uri),
+            );
+        }
+        println!("{}", "Waiting for confirmation…".dimmed());
+
+        let deadline = Instant::now() + Duration::from_secs(expires_in);
+        let mut interval = interval.unwrap_or(DEFAULT_POLL_INTERVAL_SECS);
+        loop {
+            thread::sleep(Duration::from_secs(interval));
+            if Instant::now() >= deadline {
+                return Err(CommandError::with_message_and_help(
+                    "Login code expired.".to_string(),
+                    format!("{} {}", "Run".dimmed(), "deployc login --web".bold()),
+                ));
+            }
+
+            match client.device_token(&device_code) {
+                Ok(token) => return Ok(token),
+                Err(err) => match err.code() {
+                    Some("authorization_pending") => {}
+                    Some("slow_down") => interval += SLOW_DOWN_SECS,
+                    _ => return Err(err.into()),
+                },
+            }
+        }
+    }
+
+    pub fn run(
+        matches: &ArgMatches,
+        config: &mut Config,
+        client: &ApiClient,
+    ) -> Result<(), CommandError> {
+        if matches.is_present("token-stdin") {
+            return LoginCommand::login_with_token(config);
+        }
+
         let TokenResponse {
             token,
             issued_at,
             expires_at,
-        } = API::new(config).login().post(&body)?;
+        } = if matches.is_present("web") {
+            LoginCommand::login_with_web(matches, client)?
+        } else {
+            LoginCommand::login_with_password(matches, client)?
+        };
         config.token = token;
+        config.token_type = TokenType::Session;
         config.token_issued_at = issued_at;
         config.token_expires_at = expires_at;
+        config.token_from_env = false;
         println!("{}", "Logged in!".green().bold());
         Ok(())
     }
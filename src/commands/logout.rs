@@ -1,12 +1,33 @@
 use colored::*;
+use deployc::api::ApiClient;
 
 use commands::CommandError;
-use config::Config;
+use config::{Config, TokenType};
 
 pub struct LogoutCommand;
 
 impl LogoutCommand {
-    pub fn run(config: &mut Config) -> Result<(), CommandError> {
+    pub fn run(config: &mut Config, client: &ApiClient) -> Result<(), CommandError> {
+        // the token isn't ours to revoke or forget, and the stored one is
+        // still there for when DEPLOYC_TOKEN is unset
+        if config.token_from_env {
+            return Err(CommandError::with_message_and_help(
+                format!("Logged in with {}.", "DEPLOYC_TOKEN".bold()),
+                format!("Unset {} to log out.", "DEPLOYC_TOKEN".bold()),
+            ));
+        }
+
+        // API tokens outlive the login, so only sessions are revoked
+        if !config.token.is_empty() && config.token_type == TokenType::Session {
+            if let Err(err) = client.logout() {
+                println!(
+                    "{} Could not revoke session on server: {}",
+                    "warning:".yellow().bold(),
+                    err
+                );
+            }
+        }
+
         config.clear()?;
         println!("{}", "Logged out.".green().bold());
         Ok(())
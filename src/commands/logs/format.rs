@@ -0,0 +1,167 @@
+use std::str::FromStr;
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, FixedOffset, Local, Utc};
+use chrono_humanize::HumanTime;
+use clap::ArgMatches;
+use colored::*;
+
+use commands::logs::line::LogLine;
+use commands::CommandError;
+use output::Output;
+
+const DEFAULT_TIME_FORMAT: &str = "%a, %e %b %Y %H:%M:%S";
+// green and red are left out, since they are used for timestamps and errors
+const SOURCE_COLORS: [Color; 6] = [
+    Color::Blue,
+    Color::Cyan,
+    Color::Magenta,
+    Color::Yellow,
+    Color::BrightBlue,
+    Color::BrightMagenta,
+];
+
+pub enum Timestamps {
+    Local,
+    Utc,
+    Relative,
+    Raw,
+    None,
+}
+
+impl FromStr for Timestamps {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "local" => Ok(Timestamps::Local),
+            "utc" => Ok(Timestamps::Utc),
+            "relative" => Ok(Timestamps::Relative),
+            "raw" => Ok(Timestamps::Raw),
+            "none" => Ok(Timestamps::None),
+            _ => Err(format!(
+                "Expected 'local', 'utc', 'relative', 'raw', or 'none'. Found '{}'",
+                s
+            )),
+        }
+    }
+}
+
+/// Picks a colour for a source from a hash of its name, so each replica or
+/// service keeps the same colour across runs.
+fn source_color(source: &str) -> Color {
+    let hash = source.trim().bytes().fold(2_166_136_261u32, |h, b| {
+        (h ^ u32::from(b)).wrapping_mul(16_777_619)
+    });
+    SOURCE_COLORS[hash as usize % SOURCE_COLORS.len()]
+}
+
+/// How each log line is rendered.
+pub struct LogFormat {
+    timestamps: Timestamps,
+    time_format: String,
+    json: bool,
+    prefix: bool,
+}
+
+impl LogFormat {
+    pub fn from_matches(matches: &ArgMatches) -> Result<LogFormat, CommandError> {
+        let timestamps = value_t!(matches, "timestamps", Timestamps)
+            .map_err(|err| CommandError::with_message(err.message))?;
+        let time_format = matches
+            .value_of("time-format")
+            .unwrap_or(DEFAULT_TIME_FORMAT)
+            .to_string();
+        if StrftimeItems::new(&time_format).any(|item| item == Item::Error) {
+            return Err(CommandError::with_message(format!(
+                "Invalid time format: {}",
+                time_format
+            )));
+        }
+
+        // log lines are streamed, so they can't be put in a table or a YAML document
+        let json = match Output::from_matches(matches, Output::Table)? {
+            Output::Table => false,
+            Output::Json => true,
+            _ => {
+                return Err(CommandError::with_message(
+                    "Logs can only be output as text or json.",
+                ))
+            }
+        };
+
+        Ok(LogFormat {
+            timestamps,
+            time_format,
+            json,
+            prefix: !matches.is_present("no-prefix"),
+        })
+    }
+
+    fn format_timestamp(&self, ts: DateTime<FixedOffset>, raw: &str) -> Option<String> {
+        match self.timestamps {
+            Timestamps::Local => Some(
+                ts.with_timezone(&Local)
+                    .format(&self.time_format)
+                    .to_string(),
+            ),
+            Timestamps::Utc => Some(ts.with_timezone(&Utc).format(&self.time_format).to_string()),
+            Timestamps::Relative => Some(format!("{}", HumanTime::from(ts.with_timezone(&Local)))),
+            Timestamps::Raw => Some(raw.to_string()),
+            Timestamps::None => None,
+        }
+    }
+
+    fn format_message(line: &LogLine) -> String {
+        let structured = match line.structured {
+            Some(ref s) => s,
+            None => return line.message.to_string(),
+        };
+
+        let mut parts = vec![];
+        if let Some(level) = structured.level {
+            parts.push(format!(
+                "{}",
+                format!("{:5}", level.as_str().to_uppercase())
+                    .color(level.color())
+                    .bold()
+            ));
+        }
+        if !structured.message.is_empty() {
+            parts.push(structured.message.clone());
+        }
+        for (k, v) in &structured.fields {
+            parts.push(format!("{}{}", format!("{}=", k).dimmed(), v));
+        }
+        parts.join(" ")
+    }
+
+    pub fn print(&self, line: &LogLine) {
+        if self.json {
+            let out = json!({
+                "source": line.source.trim(),
+                "timestamp": line.timestamp.map(|(_, raw)| raw),
+                "message": line.message
+            });
+            println!("{}", out);
+            return;
+        }
+
+        let mut parts = vec![];
+        if self.prefix {
+            let prefix = format!("{} |", line.source);
+            parts.push(format!(
+                "{}",
+                prefix.color(source_color(line.source)).bold()
+            ));
+        }
+        if let Some(ts) = line
+            .timestamp
+            .and_then(|(ts, raw)| self.format_timestamp(ts, raw))
+        {
+            parts.push(format!("{}", format!("[{}]", ts).green().bold()));
+        }
+        parts.push(LogFormat::format_message(line));
+        println!("{}", parts.join(" "));
+    }
+}
@@ -0,0 +1,170 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, FixedOffset};
+use colored::Color;
+use regex::Regex;
+use serde_json::{self, Map, Value};
+
+lazy_static! {
+    static ref ISODATE_STRING_RE: Regex = Regex::new(
+        r"^\d{4}-[01]\d-[0-3]\dT[0-2]\d:[0-5]\d:[0-5]\d(\.\d+)?([+-][0-2]\d:[0-5]\d|Z)"
+    ).unwrap();
+}
+
+const LEVEL_KEYS: [&str; 4] = ["level", "lvl", "severity", "loglevel"];
+const MESSAGE_KEYS: [&str; 2] = ["msg", "message"];
+
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+/// An application log line written as a JSON object.
+pub struct Structured {
+    pub level: Option<Level>,
+    pub message: String,
+    pub fields: Vec<(String, String)>,
+}
+
+/// One line of the log stream: `<source>|<timestamp> <message>`.
+pub struct LogLine<'a> {
+    pub source: &'a str,
+    pub timestamp: Option<(DateTime<FixedOffset>, &'a str)>,
+    pub message: &'a str,
+    pub structured: Option<Structured>,
+}
+
+impl Level {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Level::Trace => "trace",
+            Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Warn => "warn",
+            Level::Error => "error",
+            Level::Fatal => "fatal",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Level::Trace | Level::Debug => Color::BrightBlack,
+            Level::Info => Color::Green,
+            Level::Warn => Color::Yellow,
+            Level::Error | Level::Fatal => Color::Red,
+        }
+    }
+
+    fn from_value(value: &Value) -> Option<Level> {
+        match value {
+            Value::String(s) => s.to_lowercase().parse().ok(),
+            // bunyan and pino use numeric levels
+            Value::Number(n) => n.as_u64().map(|n| match n {
+                0..=10 => Level::Trace,
+                11..=20 => Level::Debug,
+                21..=30 => Level::Info,
+                31..=40 => Level::Warn,
+                41..=50 => Level::Error,
+                _ => Level::Fatal,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "trace" => Ok(Level::Trace),
+            "debug" => Ok(Level::Debug),
+            "info" => Ok(Level::Info),
+            "warn" | "warning" => Ok(Level::Warn),
+            "error" | "err" => Ok(Level::Error),
+            "fatal" | "critical" | "crit" | "panic" => Ok(Level::Fatal),
+            _ => Err(format!(
+                "Expected 'trace', 'debug', 'info', 'warn', 'error', or 'fatal'. Found '{}'",
+                s
+            )),
+        }
+    }
+}
+
+impl Structured {
+    fn parse(message: &str) -> Option<Structured> {
+        if !message.trim().starts_with('{') {
+            return None;
+        }
+
+        let mut map: Map<String, Value> = serde_json::from_str(message).ok()?;
+        let level = LEVEL_KEYS
+            .iter()
+            .filter_map(|k| map.remove(*k))
+            .next()
+            .and_then(|v| Level::from_value(&v));
+        let message = MESSAGE_KEYS
+            .iter()
+            .filter_map(|k| map.remove(*k))
+            .next()
+            .map_or_else(String::new, |v| match v {
+                Value::String(s) => s,
+                v => v.to_string(),
+            });
+        let fields = map
+            .into_iter()
+            .map(|(k, v)| {
+                let v = match v {
+                    Value::String(ref s) if !s.contains(char::is_whitespace) => s.clone(),
+                    v => v.to_string(),
+                };
+                (k, v)
+            })
+            .collect();
+
+        Some(Structured {
+            level,
+            message,
+            fields,
+        })
+    }
+}
+
+impl<'a> LogLine<'a> {
+    pub fn parse(raw: &'a str) -> Option<LogLine<'a>> {
+        let v: Vec<_> = raw.splitn(2, '|').collect();
+        let (source, line) = match v[..] {
+            [source, line] => (source, line),
+            _ => return None,
+        };
+
+        let parsed = ISODATE_STRING_RE.find(line).and_then(|mat| {
+            DateTime::parse_from_rfc3339(mat.as_str())
+                .ok()
+                .map(|ts| (mat, ts))
+        });
+        let (timestamp, message) = match parsed {
+            Some((mat, ts)) => (
+                Some((ts, mat.as_str())),
+                line.get(mat.end() + 1..).unwrap_or(""),
+            ),
+            None => (None, line),
+        };
+
+        Some(LogLine {
+            source,
+            timestamp,
+            message,
+            structured: Structured::parse(message),
+        })
+    }
+
+    pub fn level(&self) -> Option<Level> {
+        self.structured.as_ref().and_then(|s| s.level)
+    }
+}
@@ -0,0 +1,269 @@
+use std::cmp;
+use std::collections::HashSet;
+use std::io::{self, BufRead};
+use std::thread;
+use std::time;
+
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use clap::ArgMatches;
+use colored::*;
+use deployc::api::{ApiClient, RequestError};
+use deployc::app::App;
+use regex::Regex;
+use reqwest::Response;
+
+use commands::{CommandError, EXIT_NETWORK};
+
+mod format;
+mod line;
+mod save;
+
+use self::format::LogFormat;
+use self::line::{Level, LogLine};
+use self::save::LogFile;
+
+const DEFAULT_MAX_SIZE: &str = "10M";
+const DEFAULT_KEEP: usize = 5;
+const INITIAL_BACKOFF_SECS: u64 = 1;
+const MAX_BACKOFF_SECS: u64 = 30;
+
+lazy_static! {
+    static ref RELATIVE_TIME_RE: Regex = Regex::new(r"^(\d+)([smhd])$").unwrap();
+}
+
+/// Client-side filters, applied to lines after they have been parsed.
+struct LineFilter {
+    pattern: Option<Regex>,
+    invert: bool,
+    level: Option<Level>,
+}
+
+impl LineFilter {
+    fn matches(&self, line: &LogLine) -> bool {
+        if let Some(ref pattern) = self.pattern {
+            if pattern.is_match(line.message) == self.invert {
+                return false;
+            }
+        }
+
+        // lines without a level, such as panics and stack traces, are kept
+        match (self.level, line.level()) {
+            (Some(min), Some(level)) => level >= min,
+            _ => true,
+        }
+    }
+}
+
+/// Remembers where the log stream got to, so that a reconnect can resume from
+/// the last timestamp without repeating or dropping lines.
+#[derive(Default)]
+struct Cursor {
+    last_ts: Option<DateTime<FixedOffset>>,
+    seen_at_last_ts: HashSet<String>,
+    resuming: bool,
+}
+
+impl Cursor {
+    /// Records a line, returning false if it was already shown before reconnecting.
+    fn advance(&mut self, ts: DateTime<FixedOffset>, raw: &str) -> bool {
+        match self.last_ts {
+            Some(last) if self.resuming && ts < last => false,
+            Some(last) if ts == last => {
+                self.seen_at_last_ts.insert(raw.to_string()) || !self.resuming
+            }
+            Some(last) if ts < last => true,
+            _ => {
+                self.last_ts = Some(ts);
+                self.seen_at_last_ts.clear();
+                self.seen_at_last_ts.insert(raw.to_string());
+                self.resuming = false;
+                true
+            }
+        }
+    }
+
+    fn resume_from(&self) -> Option<String> {
+        self.last_ts.map(|ts| ts.to_rfc3339())
+    }
+}
+
+pub struct LogsCommand;
+
+impl LogsCommand {
+    fn invalid_time(s: &str) -> CommandError {
+        CommandError::with_message_and_help(
+            format!("Invalid time: {}", s),
+            "Use a duration such as 30s, 15m, 1h or 2d, or a time such as 2018-06-01T10:00."
+                .to_string(),
+        )
+    }
+
+    fn parse_time(s: &str) -> Result<DateTime<Utc>, CommandError> {
+        if let Some(caps) = RELATIVE_TIME_RE.captures(s) {
+            let unit_secs = match &caps[2] {
+                "s" => 1,
+                "m" => 60,
+                "h" => 60 * 60,
+                _ => 24 * 60 * 60,
+            };
+            // too long ago to count is as invalid as not a number
+            let since = caps[1]
+                .parse::<i64>()
+                .ok()
+                .and_then(|n| n.checked_mul(unit_secs))
+                .filter(|&secs| secs <= Duration::max_value().num_seconds())
+                .and_then(|secs| Utc::now().checked_sub_signed(Duration::seconds(secs)));
+            return since.ok_or_else(|| LogsCommand::invalid_time(s));
+        }
+
+        if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+            return Ok(dt.with_timezone(&Utc));
+        }
+
+        // times without an offset are in local time
+        let naive = NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S")
+            .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M"))
+            .or_else(|_| NaiveDate::parse_from_str(s, "%Y-%m-%d").map(|d| d.and_hms(0, 0, 0)));
+        let local = naive
+            .ok()
+            .and_then(|n| Local.from_local_datetime(&n).single());
+        match local {
+            Some(dt) => Ok(dt.with_timezone(&Utc)),
+            None => Err(LogsCommand::invalid_time(s)),
+        }
+    }
+
+    fn read_response(
+        res: Response,
+        filter: &LineFilter,
+        format: &LogFormat,
+        cursor: &mut Cursor,
+        mut save: Option<&mut LogFile>,
+    ) -> Result<(), CommandError> {
+        let reader = io::BufReader::new(res);
+        for raw in reader.lines() {
+            let raw = raw.map_err(|err| {
+                CommandError::with_message(format!("Lost connection to log stream: {}", err))
+                    .with_code(EXIT_NETWORK)
+            })?;
+            let line = match LogLine::parse(&raw) {
+                Some(line) => line,
+                None => continue,
+            };
+
+            if let Some((ts, _)) = line.timestamp {
+                if !cursor.advance(ts, &raw) {
+                    continue;
+                }
+            }
+
+            if let Some(ref mut file) = save {
+                file.write_line(&raw)?;
+            }
+
+            if !filter.matches(&line) {
+                continue;
+            }
+
+            format.print(&line);
+        }
+
+        Ok(())
+    }
+
+    pub fn run(matches: &ArgMatches, client: &ApiClient, app: &App) -> Result<(), CommandError> {
+        let follow = matches.is_present("follow");
+
+        let mut query: Vec<(&str, String)> =
+            vec![("follow", if follow { "1" } else { "0" }.to_string())];
+        if let Ok(lines) = value_t!(matches, "tail", u32) {
+            query.push(("tail", format!("{}", lines)));
+        }
+        if let Some(since) = matches.value_of("since") {
+            query.push(("since", LogsCommand::parse_time(since)?.to_rfc3339()));
+        }
+        if let Some(until) = matches.value_of("until") {
+            query.push(("until", LogsCommand::parse_time(until)?.to_rfc3339()));
+        }
+        if let Some(service) = matches.value_of("service") {
+            query.push(("service", service.to_string()));
+        }
+
+        let pattern =
+            match matches.value_of("grep") {
+                Some(pattern) => Some(Regex::new(pattern).map_err(|err| {
+                    CommandError::with_message(format!("Invalid pattern: {}", err))
+                })?),
+                None => None,
+            };
+        let level = match matches.value_of("level") {
+            Some(level) => Some(level.parse().map_err(CommandError::with_message)?),
+            None => None,
+        };
+        let filter = LineFilter {
+            pattern,
+            invert: matches.is_present("invert"),
+            level,
+        };
+
+        let format = LogFormat::from_matches(matches)?;
+        let mut save = match matches.value_of("save") {
+            Some(path) => {
+                let max_size =
+                    save::parse_size(matches.value_of("max-size").unwrap_or(DEFAULT_MAX_SIZE))?;
+                let keep = match matches.value_of("keep") {
+                    Some(keep) => keep.parse().map_err(|_| {
+                        CommandError::with_message("Invalid number of files to keep.")
+                    })?,
+                    None => DEFAULT_KEEP,
+                };
+                Some(LogFile::open(
+                    path,
+                    max_size,
+                    keep,
+                    matches.is_present("compress"),
+                )?)
+            }
+            None => None,
+        };
+
+        let mut cursor = Cursor::default();
+        let mut backoff = INITIAL_BACKOFF_SECS;
+        loop {
+            let mut query = query.clone();
+            if let Some(since) = cursor.resume_from() {
+                // already showed the tail, so pick up exactly where we left off
+                query.retain(|&(k, _)| k != "tail" && k != "since");
+                query.push(("since", since));
+            }
+
+            match client.logs(&app.name, &query) {
+                Ok(res) => {
+                    backoff = INITIAL_BACKOFF_SECS;
+                    let read = LogsCommand::read_response(
+                        res,
+                        &filter,
+                        &format,
+                        &mut cursor,
+                        save.as_mut(),
+                    );
+                    match read {
+                        Ok(()) if !follow => return Ok(()),
+                        // only a lost connection is worth reconnecting for
+                        Err(ref err) if follow && err.code == EXIT_NETWORK => {}
+                        Err(err) => return Err(err),
+                        Ok(()) => {}
+                    }
+                }
+                // the stream may just be restarting, but errors about the request itself won't go away
+                Err(RequestError::Network(_)) | Err(RequestError::Server(_)) if follow => {}
+                Err(err) => return Err(err.into()),
+            }
+
+            eprintln!("{}", format!("reconnecting in {}s…", backoff).dimmed());
+            thread::sleep(time::Duration::from_secs(backoff));
+            backoff = cmp::min(backoff * 2, MAX_BACKOFF_SECS);
+            cursor.resuming = true;
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use libflate::gzip;
+
+use commands::CommandError;
+
+/// A log file on disk that is rotated once it grows past `max_size`, keeping
+/// `keep` older files as `<path>.1`, `<path>.2`, … (with `.gz` when compressed).
+pub struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    keep: usize,
+    compress: bool,
+}
+
+/// Parses sizes such as `500K`, `10M` or `1G` into bytes.
+pub fn parse_size(s: &str) -> Result<u64, CommandError> {
+    let s = s.trim();
+    let (digits, multiplier) = match s.chars().last().map(|c| c.to_ascii_uppercase()) {
+        Some('K') => (&s[..s.len() - 1], 1 << 10),
+        Some('M') => (&s[..s.len() - 1], 1 << 20),
+        Some('G') => (&s[..s.len() - 1], 1 << 30),
+        _ => (s, 1),
+    };
+
+    // too large to count in bytes is as invalid as not a number
+    let size = digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier));
+    match size {
+        Some(n) if n > 0 => Ok(n),
+        _ => Err(CommandError::with_message_and_help(
+            format!("Invalid size: {}", s),
+            "Use a number of bytes, optionally followed by K, M or G.".to_string(),
+        )),
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+impl LogFile {
+    pub fn open(
+        path: &str,
+        max_size: u64,
+        keep: usize,
+        compress: bool,
+    ) -> Result<LogFile, CommandError> {
+        let path = PathBuf::from(path);
+        let file = open_append(&path).map_err(|err| {
+            CommandError::with_message(format!("Could not open {}: {}", path.display(), err))
+        })?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+
+        Ok(LogFile {
+            path,
+            file,
+            size,
+            max_size,
+            keep,
+            compress,
+        })
+    }
+
+    /// Appends a line, rotating first if it would grow past `max_size`.
+    pub fn write_line(&mut self, raw: &str) -> Result<(), CommandError> {
+        self.append(raw).map_err(|err| {
+            CommandError::with_message(format!(
+                "Could not save logs to {}: {}",
+                self.path.display(),
+                err
+            ))
+        })
+    }
+
+    fn append(&mut self, raw: &str) -> io::Result<()> {
+        let len = raw.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.max_size {
+            self.rotate()?;
+        }
+
+        writeln!(self.file, "{}", raw)?;
+        self.size += len;
+        Ok(())
+    }
+
+    fn rotated_path(&self, n: usize) -> PathBuf {
+        let ext = if self.compress { ".gz" } else { "" };
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}{}", n, ext));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.keep > 0 {
+            let oldest = self.rotated_path(self.keep);
+            if oldest.exists() {
+                fs::remove_file(oldest)?;
+            }
+            for n in (1..self.keep).rev() {
+                let from = self.rotated_path(n);
+                if from.exists() {
+                    fs::rename(from, self.rotated_path(n + 1))?;
+                }
+            }
+
+            if self.compress {
+                let mut src = File::open(&self.path)?;
+                let mut encoder = gzip::Encoder::new(File::create(self.rotated_path(1))?)?;
+                io::copy(&mut src, &mut encoder)?;
+                encoder.finish().into_result()?;
+            } else {
+                fs::rename(&self.path, self.rotated_path(1))?;
+            }
+        }
+
+        self.file = File::create(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
@@ -8,24 +8,35 @@ mod list;
 mod login;
 mod logout;
 mod logs;
+mod profile;
 mod secrets;
+mod sessions;
 mod signup;
 mod tier;
+mod tokens;
 mod up;
+mod whoami;
 
+pub mod certificate;
 pub mod common;
+pub mod generate;
+pub mod ssh_key;
 
 pub use self::create::CreateCommand;
 pub use self::delete::DeleteCommand;
 pub use self::describe::DescribeCommand;
 pub use self::env::EnvCommand;
-pub use self::error::CommandError;
+pub use self::error::{CommandError, EXIT_AUTH, EXIT_NETWORK};
 pub use self::expose::ExposeCommand;
 pub use self::list::ListCommand;
 pub use self::login::LoginCommand;
 pub use self::logout::LogoutCommand;
 pub use self::logs::LogsCommand;
+pub use self::profile::ProfileCommand;
 pub use self::secrets::SecretsCommand;
+pub use self::sessions::SessionsCommand;
 pub use self::signup::SignupCommand;
 pub use self::tier::TierCommand;
+pub use self::tokens::TokensCommand;
 pub use self::up::UpCommand;
+pub use self::whoami::WhoamiCommand;
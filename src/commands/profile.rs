@@ -0,0 +1,209 @@
+use std::fs;
+use std::path::PathBuf;
+
+use clap::ArgMatches;
+use colored::*;
+use url::Url;
+
+use cli::print_table;
+use commands::CommandError;
+use config::{Config, Profiles, TokenType, DEFAULT_PROFILE};
+use credentials;
+
+/// The arguments of `profile set`, other than the profile's name.
+const TLS_SETTINGS: &[&str] = &[
+    "ca-bundle",
+    "no-ca-bundle",
+    "client-cert",
+    "no-client-cert",
+    "skip-hostname-verification",
+    "verify-hostname",
+];
+
+pub struct ProfileCommand;
+
+/// Describes whether a profile is logged in, for `profile list` and `whoami`.
+pub fn login_status(config: &Config) -> String {
+    if config.token.is_empty() {
+        return "logged out".to_string();
+    }
+
+    match config.token_type {
+        TokenType::Api => "API token".to_string(),
+        TokenType::Session => "logged in".to_string(),
+    }
+}
+
+/// The server's root URL, without the `/api/` suffix.
+pub fn server_url(config: &Config) -> Url {
+    config.endpoint.join("/").unwrap()
+}
+
+impl ProfileCommand {
+    fn no_profile(name: &str) -> CommandError {
+        CommandError::with_message_and_help(
+            format!("No profile named {}.", name),
+            format!("{} {}", "Run".dimmed(), "deployc profile list".bold()),
+        )
+    }
+
+    /// Paths are stored absolute, so the profile works from any directory.
+    fn file_path(matches: &ArgMatches, arg: &str) -> Result<Option<PathBuf>, CommandError> {
+        match matches.value_of(arg) {
+            Some(path) => fs::canonicalize(path).map(Some).map_err(|err| {
+                CommandError::with_message(format!("Could not find {}: {}", path, err))
+            }),
+            None => Ok(None),
+        }
+    }
+
+    /// Applies the TLS settings given, leaving the others as they are.
+    fn set_tls(matches: &ArgMatches, config: &mut Config) -> Result<(), CommandError> {
+        if matches.is_present("ca-bundle") {
+            config.ca_bundle = ProfileCommand::file_path(matches, "ca-bundle")?;
+        } else if matches.is_present("no-ca-bundle") {
+            config.ca_bundle = None;
+        }
+        if matches.is_present("client-cert") {
+            config.client_cert = ProfileCommand::file_path(matches, "client-cert")?;
+            config.client_key = ProfileCommand::file_path(matches, "client-key")?;
+        } else if matches.is_present("no-client-cert") {
+            config.client_cert = None;
+            config.client_key = None;
+        }
+        if matches.is_present("skip-hostname-verification") {
+            config.skip_hostname_verification = true;
+        } else if matches.is_present("verify-hostname") {
+            config.skip_hostname_verification = false;
+        }
+        Ok(())
+    }
+
+    fn add(matches: &ArgMatches) -> Result<(), CommandError> {
+        let name = matches.value_of("name").unwrap();
+        let mut profiles = Profiles::load()?;
+        if profiles.profiles.contains_key(name) {
+            return Err(CommandError::with_message(format!(
+                "Profile {} already exists.",
+                name
+            )));
+        }
+
+        let endpoint = match matches.value_of("endpoint") {
+            Some(endpoint) => {
+                Url::parse(endpoint).map_err(|_| CommandError::with_message("Invalid endpoint."))?
+            }
+            None => Config::default_endpoint()?,
+        };
+        let mut config = Config::new(endpoint);
+        config.profile = name.to_string();
+        ProfileCommand::set_tls(matches, &mut config)?;
+        profiles.profiles.insert(name.to_string(), config);
+        if matches.is_present("use") {
+            profiles.current = Some(name.to_string());
+        }
+        profiles.store()?;
+
+        println!("{}", format!("Profile {} added.", name).green().bold());
+        if !matches.is_present("use") {
+            println!(
+                "Switch to it with {}, or use {} for a single command.",
+                format!("deployc profile use {}", name).blue().bold(),
+                format!("--profile {}", name).blue().bold()
+            );
+        }
+        Ok(())
+    }
+
+    fn set(matches: &ArgMatches) -> Result<(), CommandError> {
+        if !TLS_SETTINGS.iter().any(|&arg| matches.is_present(arg)) {
+            return Err(CommandError::with_message_and_help(
+                "Nothing to set.".to_string(),
+                format!("{} {}", "Run".dimmed(), "deployc profile set --help".bold()),
+            ));
+        }
+
+        let mut profiles = Profiles::load()?;
+        let name = matches
+            .value_of("name")
+            .unwrap_or_else(|| profiles.current())
+            .to_string();
+        // the default profile works before it is stored
+        let mut config = match profiles.profiles.get(&name) {
+            Some(config) => config.clone(),
+            None if name == DEFAULT_PROFILE => Config::new(Config::default_endpoint()?),
+            None => return Err(ProfileCommand::no_profile(&name)),
+        };
+
+        ProfileCommand::set_tls(matches, &mut config)?;
+        profiles.profiles.insert(name.clone(), config);
+        profiles.store()?;
+        println!("{}", format!("Profile {} updated.", name).green().bold());
+        Ok(())
+    }
+
+    fn list() -> Result<(), CommandError> {
+        let profiles = Profiles::load()?;
+        if profiles.profiles.is_empty() {
+            println!(
+                "No profiles. Add one using {}.",
+                "deployc profile add".blue().bold()
+            );
+            return Ok(());
+        }
+
+        let store = credentials::store()?;
+        let current = profiles.current();
+        let mut rows = vec![];
+        for (name, config) in &profiles.profiles {
+            let mut config = config.clone();
+            config.token = store.get(name)?.unwrap_or_default();
+            let name = if name == current {
+                format!("{} {}", name, "(current)".dimmed())
+            } else {
+                name.clone()
+            };
+            rows.push(row![name, server_url(&config), login_status(&config)]);
+        }
+        print_table(row![Fbb => "Name", "Endpoint", "Status"], rows)
+    }
+
+    fn use_profile(matches: &ArgMatches) -> Result<(), CommandError> {
+        let name = matches.value_of("name").unwrap();
+        let mut profiles = Profiles::load()?;
+        if !profiles.profiles.contains_key(name) {
+            return Err(ProfileCommand::no_profile(name));
+        }
+
+        profiles.current = Some(name.to_string());
+        profiles.store()?;
+        println!("{}", format!("Using profile {}.", name).green().bold());
+        Ok(())
+    }
+
+    fn remove(matches: &ArgMatches) -> Result<(), CommandError> {
+        let name = matches.value_of("name").unwrap();
+        let mut profiles = Profiles::load()?;
+        if profiles.profiles.remove(name).is_none() {
+            return Err(ProfileCommand::no_profile(name));
+        }
+
+        if profiles.current() == name {
+            profiles.current = None;
+        }
+        profiles.store()?;
+        credentials::store()?.delete(name)?;
+        println!("{}", format!("Profile {} removed.", name).green().bold());
+        Ok(())
+    }
+
+    pub fn run(matches: &ArgMatches) -> Result<(), CommandError> {
+        match matches.subcommand() {
+            ("add", Some(m)) => ProfileCommand::add(m),
+            ("set", Some(m)) => ProfileCommand::set(m),
+            ("use", Some(m)) => ProfileCommand::use_profile(m),
+            ("remove", Some(m)) => ProfileCommand::remove(m),
+            _ => ProfileCommand::list(),
+        }
+    }
+}
@@ -1,86 +1,157 @@
+use std::cmp::Reverse;
 use std::fs;
-use std::iter;
+use std::io::{self, Read};
 
 use base64;
 use chrono::Local;
 use chrono_humanize::HumanTime;
 use clap::ArgMatches;
 use colored::*;
-use rand::distributions;
-use rand::{thread_rng, Rng};
+use deployc::api::ApiClient;
+use deployc::app::{App, Secret, SecretType};
 use serde_json;
 use slug::slugify;
 
-use api::API;
-use app::{App, Secret, SecretType};
-use cli::{print_table, prompt_credentials, prompt_password};
+use cli::{prompt, prompt_credentials, prompt_password, trim_line_ending};
+use commands::certificate;
+use commands::generate::{self, Charset, Size};
+use commands::ssh_key::{self, SshKeyPair};
 use commands::CommandError;
-use config::Config;
+use output::{self, Output, View};
 
-pub struct SecretsCommand;
+impl View for Secret {
+    fn titles(wide: bool) -> Vec<&'static str> {
+        if wide {
+            vec!["Name", "Type", "Created", "Keys"]
+        } else {
+            vec!["Name", "Type", "Created"]
+        }
+    }
+
+    fn cells(&self, wide: bool) -> Vec<String> {
+        let mut cells = vec![
+            self.name.clone(),
+            self.ty.as_str().to_string(),
+            HumanTime::from(self.created_at.with_timezone(&Local)).to_string(),
+        ];
+        if wide {
+            cells.push(self.ty.keys().join(", "));
+        }
+        cells
+    }
 
-#[derive(Serialize, Deserialize)]
-struct ListResponse {
-    secrets: Vec<Secret>,
+    fn name(&self) -> &str {
+        &self.name
+    }
 }
 
+pub struct SecretsCommand;
+
 impl SecretsCommand {
-    fn list(config: &Config, app: &App) -> Result<(), CommandError> {
-        let ListResponse { mut secrets } = API::new(config).app(&app.name).param("secrets").get()?;
-        if secrets.is_empty() {
-            println!(
+    fn list(matches: &ArgMatches, client: &ApiClient, app: &App) -> Result<(), CommandError> {
+        let output = Output::from_matches(matches, Output::Table)?;
+
+        let mut secrets = client.secrets(&app.name)?;
+        secrets.sort_by_key(|s| Reverse(s.created_at));
+        output::print_list(
+            output,
+            &secrets,
+            &format!(
                 "No secrets. Add a secret using {}.",
                 "deployc secret create".blue().bold()
-            );
-            return Ok(());
-        }
+            ),
+        )
+    }
 
-        secrets.sort_by(|s1, s2| s2.created_at.cmp(&s1.created_at));
-        print_table(
-            row![Fbb=>"Name", "Type", "Created"],
-            secrets
-                .iter()
-                .map(|s| {
-                    row![
-                        s.name,
-                        s.ty.as_str(),
-                        HumanTime::from(s.created_at.with_timezone(&Local))
-                    ]
-                })
-                .collect(),
+    fn generate(matches: &ArgMatches) -> Result<Vec<u8>, CommandError> {
+        let charset = value_t!(matches, "charset", Charset).map_err(|err| {
+            CommandError::with_message(format!("Invalid charset: {}", err.message))
+        })?;
+        let size = if matches.is_present("bytes") {
+            Size::Bytes(
+                value_t!(matches, "bytes", usize)
+                    .map_err(|_| CommandError::with_message("Invalid number of bytes."))?,
+            )
+        } else {
+            Size::Length(
+                value_t!(matches, "length", usize)
+                    .map_err(|_| CommandError::with_message("Invalid length."))?,
+            )
+        };
+
+        let generated = generate::generate(&charset, size)?;
+        println!(
+            "Generated {} characters {}",
+            generated.value.chars().count(),
+            format!("({:.0} bits of entropy)", generated.entropy_bits).dimmed()
         );
-        Ok(())
+        Ok(generated.value.into_bytes())
     }
 
-    fn generate(num_bytes: usize) -> String {
-        let mut rng = thread_rng();
-        iter::repeat(())
-            .map(|_| rng.sample(distributions::Alphanumeric))
-            .take(num_bytes)
-            .collect()
+    fn read_from_stdin() -> Result<Vec<u8>, CommandError> {
+        let mut bytes = vec![];
+        io::stdin().read_to_end(&mut bytes).map_err(|err| {
+            CommandError::with_message(format!("Cannot read from stdin: {}", err))
+        })?;
+        Ok(bytes)
     }
 
-    fn read_from_file(filename: String) -> Result<String, CommandError> {
+    fn read_from_file(filename: String) -> Result<Vec<u8>, CommandError> {
+        if filename == "-" {
+            return SecretsCommand::read_from_stdin();
+        }
+
         let filepath = fs::canonicalize(filename)
             .map_err(|err| CommandError::with_message(format!("Could not get filepath: {}", err)))?;
-        let bytes = fs::read(filepath)
-            .map_err(|err| CommandError::with_message(format!("Cannot read file: {}", err)))?;
-        String::from_utf8_lossy(&bytes).parse().map_err(|err| {
-            CommandError::with_message(format!("Cannot parse bytes from file: {}", err))
-        })
+        fs::read(filepath)
+            .map_err(|err| CommandError::with_message(format!("Cannot read file: {}", err)))
     }
 
-    fn encode(s: &String) -> Result<serde_json::Value, CommandError> {
-        let b64_value = base64::encode(s);
+    fn encode(bytes: &[u8]) -> Result<serde_json::Value, CommandError> {
+        let b64_value = base64::encode(bytes);
         serde_json::to_value(&b64_value).map_err(|err| {
             CommandError::with_message(format!("Could not serialize value: {}", err))
         })
     }
 
-    fn create(matches: &ArgMatches, config: &Config, app: &App) -> Result<(), CommandError> {
+    fn check_certificate(key: &[u8], cert: &[u8], force: bool) -> Result<(), CommandError> {
+        let info = certificate::inspect(key, cert)?;
+        println!("{} {}", "Subject:".bold(), info.subject);
+        if !info.sans.is_empty() {
+            println!("{} {}", "SANs:".bold(), info.sans.join(", "));
+        }
+        if let Some(exp) = info.expires_at {
+            println!(
+                "{} {} ({})",
+                "Expires:".bold(),
+                exp.with_timezone(&Local).to_rfc2822(),
+                HumanTime::from(exp.with_timezone(&Local))
+            );
+        }
+
+        if info.problems.is_empty() {
+            return Ok(());
+        }
+
+        for problem in &info.problems {
+            println!("{} {}", "warning:".yellow().bold(), problem);
+        }
+
+        if force {
+            Ok(())
+        } else {
+            Err(CommandError::with_message_and_help(
+                "Certificate failed validation.".to_string(),
+                format!("Use {} to upload it anyway.", "--force".bold()),
+            ))
+        }
+    }
+
+    fn create(matches: &ArgMatches, client: &ApiClient, app: &App) -> Result<(), CommandError> {
         let name = slugify(value_t!(matches, "name", String).unwrap());
         let ty = value_t!(matches, "type", SecretType).unwrap();
-        if matches.is_present("bytes") && !ty.is_raw() {
+        let generate = matches.is_present("length") || matches.is_present("bytes");
+        if generate && !ty.is_raw() {
             return Err(CommandError::with_message(format!(
                 "{} cannot be generated randomly.",
                 ty.as_str()
@@ -89,15 +160,16 @@ impl SecretsCommand {
 
         let value = match ty {
             SecretType::Raw => {
-                let raw = if matches.is_present("bytes") {
-                    let num_bytes = value_t!(matches, "bytes", usize)
-                        .map_err(|_| CommandError::with_message("Invalid number of bytes."))?;
-                    SecretsCommand::generate(num_bytes)
+                let raw = if generate {
+                    SecretsCommand::generate(matches)?
+                } else if matches.is_present("stdin") {
+                    SecretsCommand::read_from_stdin()?
                 } else if let Ok(filename) = value_t!(matches, "file", String) {
                     SecretsCommand::read_from_file(filename)?
                 } else {
                     prompt_password(format!("  {}{} ", name.blue().bold(), ":".blue().bold()))
                         .ok_or_else(|| CommandError::with_message("Invalid value."))?
+                        .into_bytes()
                 };
 
                 SecretsCommand::encode(&raw)?
@@ -111,48 +183,125 @@ impl SecretsCommand {
                 let cert_filename = value_t!(matches, "cert", String).unwrap();
                 let cert = SecretsCommand::read_from_file(cert_filename)?;
 
+                SecretsCommand::check_certificate(&key, &cert, matches.is_present("force"))?;
+
                 json!({
                     "tls.key": SecretsCommand::encode(&key)?,
                     "tls.cert": SecretsCommand::encode(&cert)?
                 })
             }
             SecretType::Credentials => {
+                if matches.is_present("stdin") {
+                    return Err(CommandError::with_message_and_help(
+                        "Credentials can't be read with --stdin.".to_string(),
+                        "Pipe the username and password in on separate lines instead.".to_string(),
+                    ));
+                }
                 let (username, password) = prompt_credentials()?;
                 json!({
-                    "username": SecretsCommand::encode(&username)?,
+                    "username": SecretsCommand::encode(username.as_bytes())?,
+                    "password": SecretsCommand::encode(password.as_bytes())?
+                })
+            }
+            SecretType::SshKey => {
+                let (private_key, public_key) = if matches.is_present("keygen") {
+                    let comment = format!("deployc:{}/{}", app.name, name);
+                    let SshKeyPair {
+                        private_key,
+                        public_key,
+                    } = ssh_key::generate(&comment)?;
+                    println!("{}", "Public key:".bold());
+                    println!("{}", public_key);
+                    (private_key.into_bytes(), Some(public_key))
+                } else if matches.is_present("stdin") || matches.is_present("key") {
+                    let key = if matches.is_present("stdin") {
+                        SecretsCommand::read_from_stdin()?
+                    } else {
+                        SecretsCommand::read_from_file(value_t!(matches, "key", String).unwrap())?
+                    };
+                    if !ssh_key::is_private_key(&key) {
+                        return Err(CommandError::with_message(
+                            "Not a private key. Expected a PEM-armoured SSH private key.",
+                        ));
+                    }
+                    (key, None)
+                } else {
+                    return Err(CommandError::with_message_and_help(
+                        "Missing SSH private key.".to_string(),
+                        format!(
+                            "Use {} or {} to read a key, or {} to generate one.",
+                            "--key".bold(),
+                            "--stdin".bold(),
+                            "--keygen".bold()
+                        ),
+                    ));
+                };
+
+                let mut value = json!({ "ssh-privatekey": SecretsCommand::encode(&private_key)? });
+                if let Some(public_key) = public_key {
+                    value["ssh-publickey"] = SecretsCommand::encode(public_key.as_bytes())?;
+                }
+                value
+            }
+            SecretType::Registry => {
+                let server = value_t!(matches, "server", String).unwrap();
+                let username = match value_t!(matches, "username", String) {
+                    Ok(username) => username,
+                    Err(_) => prompt("  Username: ".bold())
+                        .ok_or_else(|| CommandError::with_message("Invalid username."))?
+                        .trim()
+                        .to_string(),
+                };
+                let password = if matches.is_present("stdin") {
+                    // a password has no line ending, but `echo` adds one
+                    trim_line_ending(&SecretsCommand::read_from_stdin()?).to_vec()
+                } else {
+                    prompt_password("  Password: ".bold())
+                        .ok_or_else(|| CommandError::with_message("Invalid password."))?
+                        .into_bytes()
+                };
+
+                json!({
+                    "server": SecretsCommand::encode(server.as_bytes())?,
+                    "username": SecretsCommand::encode(username.as_bytes())?,
                     "password": SecretsCommand::encode(&password)?
                 })
             }
-        };
+            SecretType::Json => {
+                let raw = if matches.is_present("stdin") {
+                    SecretsCommand::read_from_stdin()?
+                } else if let Ok(filename) = value_t!(matches, "file", String) {
+                    SecretsCommand::read_from_file(filename)?
+                } else {
+                    return Err(CommandError::with_message_and_help(
+                        "Missing JSON value.".to_string(),
+                        format!("Use {} or {}.", "--file".bold(), "--stdin".bold()),
+                    ));
+                };
 
-        let body = json!({
-            "name": name,
-            "type": ty,
-            "value": value
-        });
+                serde_json::from_slice::<serde_json::Value>(&raw)
+                    .map_err(|err| CommandError::with_message(format!("Invalid JSON: {}", err)))?;
+                SecretsCommand::encode(&raw)?
+            }
+        };
 
-        let Secret { .. } = API::new(config)
-            .app(&app.name)
-            .param("secrets")
-            .post(&body)?;
+        client.create_secret(&app.name, &name, &ty, &value)?;
 
         println!(
-            "{} {}",
+            "{} Use secret as env var with {}",
             format!("Secret {} created!", name).green().bold(),
-            format!(
-                "Use secret as env var with {}",
-                format!("deployc env set -S MY_SECRET {}", name)
-                    .blue()
-                    .bold()
-            )
+            format!("deployc env set -S MY_SECRET {}", name)
+                .blue()
+                .bold()
         );
         Ok(())
     }
 
-    pub fn run(matches: &ArgMatches, config: &Config, app: &App) -> Result<(), CommandError> {
+    pub fn run(matches: &ArgMatches, client: &ApiClient, app: &App) -> Result<(), CommandError> {
         match matches.subcommand() {
-            ("create", Some(m)) => SecretsCommand::create(m, config, app),
-            ("list", _) | _ => SecretsCommand::list(config, app),
+            ("create", Some(m)) => SecretsCommand::create(m, client, app),
+            ("list", Some(m)) => SecretsCommand::list(m, client, app),
+            _ => SecretsCommand::list(matches, client, app),
         }
     }
 }
@@ -0,0 +1,84 @@
+use std::cmp::Reverse;
+
+use chrono::Local;
+use chrono_humanize::HumanTime;
+use clap::ArgMatches;
+use colored::*;
+use deployc::api::ApiClient;
+use deployc::models::Session;
+
+use cli::print_table;
+use commands::CommandError;
+
+pub struct SessionsCommand;
+
+impl SessionsCommand {
+    fn list(client: &ApiClient) -> Result<(), CommandError> {
+        let mut sessions = client.sessions()?;
+        sessions.sort_by_key(|s| Reverse(s.last_used_at.unwrap_or(s.created_at)));
+        print_table(
+            row![Fbb => "ID", "Device", "IP", "Created", "Last used"],
+            sessions
+                .iter()
+                .map(|s| {
+                    let id = if s.current {
+                        format!("{} {}", s.id, "(current)".dimmed())
+                    } else {
+                        s.id.clone()
+                    };
+                    let last_used = s.last_used_at.map_or_else(
+                        || "never".to_string(),
+                        |t| format!("{}", HumanTime::from(t.with_timezone(&Local))),
+                    );
+                    row![
+                        id,
+                        s.device.as_ref().map_or("unknown", |d| d.as_str()),
+                        s.ip.as_ref().map_or("", |ip| ip.as_str()),
+                        HumanTime::from(s.created_at.with_timezone(&Local)),
+                        last_used
+                    ]
+                })
+                .collect(),
+        )
+    }
+
+    fn revoke(matches: &ArgMatches, client: &ApiClient) -> Result<(), CommandError> {
+        let sessions = client.sessions()?;
+        let others: Vec<&Session> = sessions.iter().filter(|s| !s.current).collect();
+        let ids: Vec<&str> = if matches.is_present("all") {
+            others.iter().map(|s| s.id.as_str()).collect()
+        } else {
+            let id = matches.value_of("id").unwrap();
+            match sessions.iter().find(|s| s.id == id) {
+                Some(s) if s.current => {
+                    return Err(CommandError::with_message_and_help(
+                        "Cannot revoke the current session.".to_string(),
+                        format!("{} {}", "Run".dimmed(), "deployc logout".bold()),
+                    ))
+                }
+                Some(s) => vec![s.id.as_str()],
+                None => {
+                    return Err(CommandError::with_message(format!(
+                        "No session with ID {}.",
+                        id
+                    )))
+                }
+            }
+        };
+
+        for id in &ids {
+            client.revoke_session(id)?;
+        }
+        let plural = if ids.len() == 1 { "" } else { "s" };
+        let message = format!("Revoked {} session{}.", ids.len(), plural);
+        println!("{}", message.green().bold());
+        Ok(())
+    }
+
+    pub fn run(matches: &ArgMatches, client: &ApiClient) -> Result<(), CommandError> {
+        match matches.subcommand() {
+            ("revoke", Some(m)) => SessionsCommand::revoke(m, client),
+            _ => SessionsCommand::list(client),
+        }
+    }
+}
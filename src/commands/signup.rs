@@ -1,8 +1,8 @@
-use chrono::{DateTime, Utc};
 use colored::*;
+use deployc::api::ApiClient;
+use deployc::models::Signup;
 use regex::Regex;
 
-use api::API;
 use cli::{prompt, prompt_password};
 use commands::CommandError;
 use config::Config;
@@ -11,17 +11,6 @@ lazy_static! {
     static ref USERNAME_RE: Regex = Regex::new(r"^[\-_a-zA-Z0-9]+$").unwrap();
 }
 
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct SignupResponse {
-    id: String,
-    username: String,
-    email: String,
-    token: String,
-    token_issued_at: Option<DateTime<Utc>>,
-    token_expires_at: Option<DateTime<Utc>>,
-}
-
 pub struct SignupCommand;
 
 impl SignupCommand {
@@ -65,26 +54,20 @@ impl SignupCommand {
         Ok(password)
     }
 
-    pub fn run(config: &mut Config) -> Result<(), CommandError> {
+    pub fn run(config: &mut Config, client: &ApiClient) -> Result<(), CommandError> {
         // get username and password
         println!("{}", "Sign up for deployc.io".blue().bold());
         let username = SignupCommand::get_username()?;
         let email = SignupCommand::get_email()?;
         let password = SignupCommand::get_password()?;
 
-        let body = json!({
-            "username": username,
-            "email": email,
-            "password": password
-        });
-
         // make request to deployc.io
-        let SignupResponse {
+        let Signup {
             token,
             token_issued_at,
             token_expires_at,
             ..
-        } = API::new(config).signup().post(&body)?;
+        } = client.signup(&username, &email, &password)?;
         config.token = token;
         config.token_issued_at = token_issued_at;
         config.token_expires_at = token_expires_at;
@@ -1,47 +1,37 @@
-use std::fmt;
-
 use clap::ArgMatches;
 use colored::*;
+use deployc::api::ApiClient;
+use deployc::app::App;
+use deployc::models::Tier;
 
-use api::API;
-use app::App;
 use cli;
 use commands::common::check_card;
 use commands::CommandError;
-use config::Config;
-
-#[derive(Serialize, Deserialize)]
-struct Tier {
-    name: String,
-    pricing: String,
-}
+use output::{self, Output, View};
 
-impl fmt::Display for Tier {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{} ({})", self.name, self.pricing)
+impl View for Tier {
+    fn titles(_wide: bool) -> Vec<&'static str> {
+        vec!["Tier", "Pricing"]
     }
-}
 
-impl PartialEq for Tier {
-    fn eq(&self, other: &Tier) -> bool {
-        self.name == other.name
+    fn cells(&self, _wide: bool) -> Vec<String> {
+        vec![self.name.clone(), self.pricing.clone()]
     }
-}
 
-#[derive(Serialize, Deserialize)]
-struct ListTiersResponse {
-    tiers: Vec<Tier>,
+    fn name(&self) -> &str {
+        &self.name
+    }
 }
 
 pub struct TierCommand;
 
 impl TierCommand {
-    pub fn run(matches: &ArgMatches, config: &Config, app: &App) -> Result<(), CommandError> {
-        let current_tier = API::new(config).app(&app.name).param("tier").get()?;
-        if let Some(_) = matches.subcommand_matches("upgrade") {
-            check_card(config)?;
+    pub fn run(matches: &ArgMatches, client: &ApiClient, app: &App) -> Result<(), CommandError> {
+        let current_tier = client.tier(&app.name)?;
+        if matches.subcommand_matches("upgrade").is_some() {
+            check_card(client)?;
 
-            let ListTiersResponse { tiers } = API::new(config).tiers().get()?;
+            let tiers = client.tiers()?;
             println!(
                 "Choose a tier. {}",
                 "(ESC or Ctrl-C to cancel)".dimmed().bold()
@@ -52,9 +42,7 @@ impl TierCommand {
                 println!("{} Tier unchanged.", "note:".cyan().bold());
             }
         } else {
-            let Tier { name, pricing } = current_tier;
-            println!("{} {}", "Tier:".bold(), name);
-            println!("{} {}", "Pricing:".bold(), pricing);
+            output::print_one(Output::from_matches(matches, Output::Table)?, &current_tier)?;
         }
 
         Ok(())
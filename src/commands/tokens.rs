@@ -0,0 +1,95 @@
+use std::cmp::Reverse;
+
+use chrono::{DateTime, Duration, Local, Utc};
+use chrono_humanize::HumanTime;
+use clap::ArgMatches;
+use colored::*;
+use deployc::api::ApiClient;
+use deployc::models::CreatedToken;
+
+use cli::print_table;
+use commands::CommandError;
+
+fn humanize(time: Option<DateTime<Utc>>, none: &str) -> String {
+    time.map_or_else(
+        || none.to_string(),
+        |t| format!("{}", HumanTime::from(t.with_timezone(&Local))),
+    )
+}
+
+pub struct TokensCommand;
+
+impl TokensCommand {
+    fn create(matches: &ArgMatches, client: &ApiClient) -> Result<(), CommandError> {
+        let name = matches.value_of("name").unwrap();
+        let scopes: Vec<&str> = matches.values_of("scope").unwrap().collect();
+        let expires_at = match matches.value_of("expires-in") {
+            Some(days) => {
+                let days: i64 = days.parse().map_err(|_| {
+                    CommandError::with_message("Invalid expiry. Expected a number of days.")
+                })?;
+                Some(Utc::now() + Duration::days(days))
+            }
+            None => None,
+        };
+
+        let CreatedToken { token, secret } =
+            client.create_token(name, &scopes, expires_at)?;
+
+        println!(
+            "{}",
+            format!("Token {} created!", token.name).green().bold()
+        );
+        println!("{}", secret);
+        println!(
+            "{} This is the only time the token is shown. Use it with {} or {}.",
+            "note:".cyan().bold(),
+            "DEPLOYC_TOKEN".bold(),
+            "deployc login --token-stdin".bold()
+        );
+        Ok(())
+    }
+
+    fn list(client: &ApiClient) -> Result<(), CommandError> {
+        let mut tokens = client.tokens()?;
+        if tokens.is_empty() {
+            println!(
+                "No API tokens. Create one using {}.",
+                "deployc tokens create".blue().bold()
+            );
+            return Ok(());
+        }
+
+        tokens.sort_by_key(|t| Reverse(t.created_at));
+        print_table(
+            row![Fbb => "Name", "Scopes", "Created", "Last used", "Expires"],
+            tokens
+                .iter()
+                .map(|t| {
+                    row![
+                        t.name,
+                        t.scopes.join(","),
+                        HumanTime::from(t.created_at.with_timezone(&Local)),
+                        humanize(t.last_used_at, "never"),
+                        humanize(t.expires_at, "never")
+                    ]
+                })
+                .collect(),
+        )
+    }
+
+    fn revoke(matches: &ArgMatches, client: &ApiClient) -> Result<(), CommandError> {
+        let name = matches.value_of("name").unwrap();
+        client.revoke_token(name)?;
+        println!("{}", format!("Token {} revoked.", name).green().bold());
+        Ok(())
+    }
+
+    pub fn run(matches: &ArgMatches, client: &ApiClient) -> Result<(), CommandError> {
+        match matches.subcommand() {
+            ("create", Some(m)) => TokensCommand::create(m, client),
+            ("revoke", Some(m)) => TokensCommand::revoke(m, client),
+            _ => TokensCommand::list(client),
+        }
+    }
+}
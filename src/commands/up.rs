@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::fs::File;
 use std::io::{self, BufRead};
 use std::path::PathBuf;
@@ -6,15 +7,14 @@ use chrono::Local;
 use chrono_humanize::{Accuracy, HumanTime, Tense};
 use clap::ArgMatches;
 use colored::*;
+use deployc::api::ApiClient;
+use deployc::app::App;
 use reqwest::mime;
 use reqwest::multipart::{Form, Part};
-use reqwest::Method;
 use reqwest::Response;
 use serde_json;
 use tar;
 
-use api::API;
-use app::App;
 use commands::common::check_card;
 use commands::CommandError;
 use config::Config;
@@ -22,6 +22,57 @@ use config::Config;
 pub struct UpCommand;
 
 impl UpCommand {
+    fn check_files(client: &ApiClient, app: &App) -> Result<(), CommandError> {
+        if app.files.is_empty() {
+            return Ok(());
+        }
+
+        let secrets = client.secrets(&app.name)?;
+        let mut paths = HashSet::new();
+        for mount in &app.files {
+            if !mount.path.starts_with('/') {
+                return Err(CommandError::with_message(format!(
+                    "Invalid path for secret {}: {} must be absolute.",
+                    mount.secret.bold(),
+                    mount.path
+                )));
+            }
+            if !paths.insert(&mount.path) {
+                return Err(CommandError::with_message(format!(
+                    "Path {} is mounted more than once.",
+                    mount.path.bold()
+                )));
+            }
+
+            let secret = secrets
+                .iter()
+                .find(|s| s.name == mount.secret)
+                .ok_or_else(|| {
+                    CommandError::with_message_and_help(
+                        format!("Secret {} does not exist.", mount.secret.bold()),
+                        format!("Create it with {}.", "deployc secret create".blue().bold()),
+                    )
+                })?;
+
+            if let Some(ref key) = mount.key {
+                let keys = secret.ty.keys();
+                if !keys.contains(&key.as_str()) {
+                    let help = if keys.is_empty() {
+                        format!("{} secrets have no keys.", secret.ty.as_str())
+                    } else {
+                        format!("Expected one of: {}.", keys.join(", "))
+                    };
+                    return Err(CommandError::with_message_and_help(
+                        format!("Secret {} has no key {}.", mount.secret.bold(), key.bold()),
+                        help,
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     fn tar_app(app: &App) -> Result<PathBuf, CommandError> {
         // create tar file
         let path = Config::dir()
@@ -43,14 +94,9 @@ impl UpCommand {
         Ok(path)
     }
 
-    fn create_form(path: &PathBuf, app: &App) -> Result<Form, CommandError> {
-        let raw_app = serde_json::to_string(&app)
-            .map_err(|_| CommandError::with_message("Could not write app config to form."))?;
-        let app_part = Part::text(raw_app).mime(mime::APPLICATION_JSON);
-        Form::new()
-            .part("config", app_part)
-            .file("file", path)
-            .map_err(|_| CommandError::with_message("Could not add tar file to request."))
+    fn create_form(path: &PathBuf, raw_app: &str) -> io::Result<Form> {
+        let app_part = Part::text(raw_app.to_string()).mime(mime::APPLICATION_JSON);
+        Form::new().part("config", app_part).file("file", path)
     }
 
     fn read_response(res: Response) -> Result<(), CommandError> {
@@ -63,16 +109,16 @@ impl UpCommand {
                 println!(
                     "{} {}",
                     build_prefix,
-                    line.trim_left_matches("[stdout]").trim_left()
+                    line.trim_start_matches("[stdout]").trim_start()
                 );
             } else if line.starts_with("[stderr]") {
                 eprintln!(
                     "{} {}",
                     build_prefix,
-                    line.trim_left_matches("[stderr]").trim_left().red()
+                    line.trim_start_matches("[stderr]").trim_start().red()
                 );
             } else if line.starts_with("[result]") {
-                let result = line.trim_left_matches("[result]").trim_left();
+                let result = line.trim_start_matches("[result]").trim_start();
                 if result == "FAILED" {
                     return Err(CommandError::with_message("Failed to deploy."));
                 } else {
@@ -92,7 +138,7 @@ impl UpCommand {
             }
         }
 
-        if error_lines.len() > 0 {
+        if !error_lines.is_empty() {
             Err(CommandError::with_message(format!(
                 "Failed to deploy:\n{}",
                 error_lines.join("\n")
@@ -102,18 +148,14 @@ impl UpCommand {
         }
     }
 
-    pub fn run(_matches: &ArgMatches, config: &Config, app: &App) -> Result<(), CommandError> {
-        check_card(config)?;
+    pub fn run(_matches: &ArgMatches, client: &ApiClient, app: &App) -> Result<(), CommandError> {
+        check_card(client)?;
+        UpCommand::check_files(client, app)?;
 
         let path = UpCommand::tar_app(app)?;
-        let form = UpCommand::create_form(&path, app)?;
-        let res = API::new(config)
-            .apps()
-            .param(&format!("{}/up", app.name))
-            .request(Method::Post)
-            .multipart(form)
-            .send()?;
-
+        let raw_app = serde_json::to_string(&app)
+            .map_err(|_| CommandError::with_message("Could not write app config to form."))?;
+        let res = client.up(&app.name, || UpCommand::create_form(&path, &raw_app))?;
         UpCommand::read_response(res)
     }
 }
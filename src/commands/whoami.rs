@@ -0,0 +1,51 @@
+use chrono::{DateTime, Local, Utc};
+use chrono_humanize::HumanTime;
+use colored::*;
+use deployc::api::ApiClient;
+use deployc::models::User;
+
+use commands::profile::{login_status, server_url};
+use commands::CommandError;
+use config::Config;
+
+fn print_time(label: &str, time: Option<DateTime<Utc>>) {
+    if let Some(time) = time {
+        let local = time.with_timezone(&Local);
+        println!(
+            "{} {} {}",
+            label.bold(),
+            local.to_rfc2822(),
+            format!("({})", HumanTime::from(local)).dimmed()
+        );
+    }
+}
+
+pub struct WhoamiCommand;
+
+impl WhoamiCommand {
+    pub fn run(config: &Config, client: &ApiClient) -> Result<(), CommandError> {
+        if config.token.is_empty() {
+            println!("{} {}", "Profile:".bold(), config.profile);
+            println!("{} {}", "Endpoint:".bold(), server_url(config));
+            return Err(CommandError::with_message_and_help(
+                "Not logged in.".to_string(),
+                format!("{} {}", "Run".dimmed(), "deployc login".bold()),
+            ));
+        }
+
+        let User { username, email } = client.me()?;
+        println!("{} {}", "Username:".bold(), username);
+        println!("{} {}", "Email:".bold(), email);
+        println!("{} {}", "Profile:".bold(), config.profile);
+        println!("{} {}", "Endpoint:".bold(), server_url(config));
+
+        let mut status = login_status(config);
+        if config.token_from_env {
+            status = format!("{} (from DEPLOYC_TOKEN)", status);
+        }
+        println!("{} {}", "Status:".bold(), status);
+        print_time("Issued:", config.token_issued_at);
+        print_time("Expires:", config.token_expires_at);
+        Ok(())
+    }
+}
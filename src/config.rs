@@ -1,58 +1,308 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
-use std::{env, fs};
+use std::{env, fs, mem, time};
 
 use chrono::{DateTime, Duration, Utc};
 use colored::*;
-use reqwest::header::{Authorization, Bearer};
+use deployc::api::{ApiClient, RequestOptions, TlsOptions};
 use serde_json;
 use url::Url;
 use url_serde;
 
 use commands::CommandError;
+use credentials;
+use session;
 
-const DEFAULT_ENDPOINT: &'static str = "https://deployc.io/";
-// Anytime after 8 hours, the token will try to be refreshed
+const DEFAULT_ENDPOINT: &str = "https://deployc.io/";
+pub const DEFAULT_PROFILE: &str = "default";
+// Without an expiry time, the token will try to be refreshed anytime after 8 hours
 const REFRESH_THRESHOLD_HOURS: i64 = 8;
+// With only an expiry time, the token will try to be refreshed in its last hour
+const REFRESH_BEFORE_EXPIRY_HOURS: i64 = 1;
 
-#[derive(Serialize, Deserialize)]
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
+/// Parses a timeout in seconds, where 0 means no timeout.
+pub fn parse_timeout(secs: &str) -> Result<Option<time::Duration>, CommandError> {
+    match secs.parse() {
+        Ok(0) => Ok(None),
+        Ok(secs) => Ok(Some(time::Duration::from_secs(secs))),
+        Err(_) => Err(CommandError::with_message(format!(
+            "Invalid timeout: {}. Expected a number of seconds.",
+            secs
+        ))),
+    }
+}
+
+pub fn parse_retries(retries: &str) -> Result<u32, CommandError> {
+    retries
+        .parse()
+        .map_err(|_| CommandError::with_message(format!("Invalid number of retries: {}.", retries)))
+}
+
+fn var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+/// `DEPLOYC_ENDPOINT`, the root URL of a deployc server, when set.
+fn env_endpoint() -> Result<Option<Url>, CommandError> {
+    match var("DEPLOYC_ENDPOINT") {
+        Some(endpoint) => Url::parse(&endpoint)
+            .map(Some)
+            .map_err(|_| CommandError::with_message("Invalid endpoint in DEPLOYC_ENDPOINT.")),
+        None => Ok(None),
+    }
+}
+
+/// Request options from `DEPLOYC_TIMEOUT`, `DEPLOYC_RETRIES` and
+/// `DEPLOYC_DEBUG`, which `--timeout`, `--retries` and `--verbose` override.
+fn request_options_from_env() -> Result<RequestOptions, CommandError> {
+    let mut options = RequestOptions::default();
+    if let Some(timeout) = var("DEPLOYC_TIMEOUT") {
+        options.read_timeout = parse_timeout(&timeout)?;
+    }
+    if let Some(retries) = var("DEPLOYC_RETRIES") {
+        options.retries = parse_retries(&retries)?;
+    }
+    options.verbose = var("DEPLOYC_DEBUG").filter(|debug| debug != "0").is_some();
+    Ok(options)
+}
+
+/// Session tokens come from `deployc login` and are refreshed periodically.
+/// API tokens are long-lived and never refreshed.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenType {
+    #[default]
+    Session,
+    Api,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Config {
     #[serde(with = "url_serde")]
     pub endpoint: Url,
+    /// Kept in the credential store. Only read from the config file to migrate
+    /// configs written before tokens were moved out of it.
+    #[serde(default, skip_serializing)]
     pub token: String,
+    #[serde(default)]
+    pub token_type: TokenType,
     pub token_issued_at: Option<DateTime<Utc>>,
     pub token_expires_at: Option<DateTime<Utc>>,
+    /// PEM file of extra CA certificates to trust, such as for a TLS inspecting proxy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_bundle: Option<PathBuf>,
+    /// PEM files of a client certificate and its private key, for servers that require one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_cert: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_key: Option<PathBuf>,
+    /// Don't check that the server's certificate is for its hostname.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub skip_hostname_verification: bool,
+    /// Name of the profile this config was loaded from.
+    #[serde(skip)]
+    pub profile: String,
+    /// Set when the token was taken from `DEPLOYC_TOKEN`; such tokens are never stored.
+    #[serde(skip)]
+    pub token_from_env: bool,
+    /// The profile's own endpoint, kept when `DEPLOYC_ENDPOINT` overrides it.
+    #[serde(skip)]
+    stored_endpoint: Option<Url>,
+    #[serde(skip)]
+    pub request_options: RequestOptions,
+}
+
+/// The contents of the config file: one `Config` per named profile.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Profiles {
+    pub current: Option<String>,
+    pub profiles: BTreeMap<String, Config>,
+}
+
+impl Profiles {
+    fn filepath() -> Option<PathBuf> {
+        Config::dir().map(|dir| dir.join("config.json"))
+    }
+
+    pub fn load() -> Result<Profiles, CommandError> {
+        let filepath = match Profiles::filepath() {
+            Some(filepath) => filepath,
+            None => return Ok(Profiles::default()),
+        };
+        let f = match fs::File::open(filepath) {
+            Ok(f) => f,
+            Err(_) => return Ok(Profiles::default()),
+        };
+
+        let value: serde_json::Value = serde_json::from_reader(f)
+            .map_err(|_| CommandError::with_message("Failed to read config file."))?;
+        let profiles = if value.get("profiles").is_some() {
+            serde_json::from_value(value)
+                .map_err(|_| CommandError::with_message("Failed to read config file."))?
+        } else {
+            // config files from before profiles hold a single config, which becomes the default profile
+            let config: Config = serde_json::from_value(value)
+                .map_err(|_| CommandError::with_message("Failed to read config file."))?;
+            let mut profiles = Profiles::default();
+            profiles
+                .profiles
+                .insert(DEFAULT_PROFILE.to_string(), config);
+            profiles
+        };
+
+        profiles.migrate_tokens()?;
+        Ok(profiles)
+    }
+
+    /// Moves any plain text tokens left in the config file to the credential store.
+    fn migrate_tokens(&self) -> Result<(), CommandError> {
+        let plaintext: Vec<_> = self
+            .profiles
+            .iter()
+            .filter(|&(_, config)| !config.token.is_empty())
+            .collect();
+        if plaintext.is_empty() {
+            return Ok(());
+        }
+
+        let store = credentials::store()?;
+        for (name, config) in plaintext {
+            store.set(name, &config.token)?;
+        }
+        self.store()
+    }
+
+    pub fn store(&self) -> Result<(), CommandError> {
+        let filepath = Config::get_dir()?.join("config.json");
+        let f = fs::File::create(filepath)
+            .map_err(|_| CommandError::with_message("Could not open config file."))?;
+        serde_json::to_writer_pretty(f, self)
+            .map_err(|_| CommandError::with_message("Could not write to config file."))
+    }
+
+    pub fn current(&self) -> &str {
+        self.current
+            .as_ref()
+            .map_or(DEFAULT_PROFILE, |current| current.as_str())
+    }
 }
 
 impl Config {
-    pub fn get() -> Result<Config, CommandError> {
-        if let Some(dir) = Config::dir() {
-            let filepath = dir.join("config.json");
-            if filepath.exists() {
-                if let Ok(f) = fs::File::open(filepath) {
-                    return serde_json::from_reader(f)
-                        .map_err(|_| CommandError::with_message("Failed to read config file."));
-                }
+    /// Loads the config for the given profile, falling back to `DEPLOYC_PROFILE`
+    /// and then the current profile.
+    ///
+    /// Environment variables win over what the profile stores, for this run
+    /// only: the endpoint is `DEPLOYC_ENDPOINT`, then the profile's, then
+    /// deployc.io, and the token is `DEPLOYC_TOKEN`, then the profile's.
+    pub fn get(profile: Option<&str>) -> Result<Config, CommandError> {
+        let profiles = Profiles::load()?;
+        let env_profile = env::var("DEPLOYC_PROFILE").ok().filter(|p| !p.is_empty());
+        let name = profile
+            .map(|p| p.to_string())
+            .or(env_profile)
+            .unwrap_or_else(|| profiles.current().to_string());
+
+        let mut config = match profiles.profiles.get(&name) {
+            Some(config) => config.clone(),
+            None if name == DEFAULT_PROFILE => Config::new(Config::default_endpoint()?),
+            None => {
+                return Err(CommandError::with_message_and_help(
+                    format!("No profile named {}.", name),
+                    format!(
+                        "{} {}",
+                        "Run".dimmed(),
+                        format!("deployc profile add {}", name).bold()
+                    ),
+                ))
+            }
+        };
+        config.token = credentials::store()?.get(&name)?.unwrap_or_default();
+        config.profile = name;
+        if let Some(endpoint) = env_endpoint()? {
+            let endpoint = endpoint.join("/api/").unwrap();
+            if endpoint != config.endpoint {
+                config.stored_endpoint = Some(mem::replace(&mut config.endpoint, endpoint));
             }
         }
+        config.request_options = request_options_from_env()?;
 
-        let url = if let Ok(u) =
-            Url::parse(&env::var("DEPLOYC_ENDPOINT").unwrap_or(DEFAULT_ENDPOINT.to_string()))
-        {
-            u
-        } else {
-            return Err(CommandError::with_message("Invalid endpoint."));
-        };
+        if let Ok(token) = env::var("DEPLOYC_TOKEN") {
+            if !token.is_empty() {
+                config.token = token;
+                config.token_type = TokenType::Api;
+                config.token_issued_at = None;
+                config.token_expires_at = None;
+                config.token_from_env = true;
+            }
+        }
 
-        let endpoint = url.join("/api/").unwrap();
-        Ok(Config {
-            endpoint,
+        Ok(config)
+    }
+
+    /// A logged out config for `endpoint`, which is the root URL of a deployc server.
+    pub fn new(endpoint: Url) -> Config {
+        Config {
+            endpoint: endpoint.join("/api/").unwrap(),
             token: "".to_string(),
+            token_type: TokenType::Session,
             token_expires_at: None,
             token_issued_at: None,
+            ca_bundle: None,
+            client_cert: None,
+            client_key: None,
+            skip_hostname_verification: false,
+            profile: DEFAULT_PROFILE.to_string(),
+            token_from_env: false,
+            stored_endpoint: None,
+            request_options: RequestOptions::default(),
+        }
+    }
+
+    /// A client for this profile's server, which renews the session when it
+    /// expires part way through a command.
+    pub fn client(&self) -> ApiClient {
+        let client = ApiClient::new(self.endpoint.clone())
+            .with_token(session::current_token(self))
+            .with_options(self.request_options.clone())
+            .with_tls(TlsOptions {
+                ca_bundle: self.ca_bundle.clone(),
+                client_cert: self.client_cert.clone(),
+                client_key: self.client_key.clone(),
+                skip_hostname_verification: self.skip_hostname_verification,
+            })
+            .on_retry(|reason, delay| {
+                eprintln!(
+                    "{}",
+                    format!(
+                        "Request failed ({}), retrying in {:.1}s…",
+                        reason,
+                        delay.as_secs() as f64 + f64::from(delay.subsec_millis()) / 1000.0
+                    ).dimmed()
+                );
+            });
+        if !session::can_renew(self) {
+            return client;
+        }
+
+        let config = self.clone();
+        client.on_unauthorized(move || {
+            session::renew(&config).map(|()| session::current_token(&config))
         })
     }
 
+    pub fn default_endpoint() -> Result<Url, CommandError> {
+        match env_endpoint()? {
+            Some(endpoint) => Ok(endpoint),
+            None => Ok(Url::parse(DEFAULT_ENDPOINT).unwrap()),
+        }
+    }
+
     pub fn dir() -> Option<PathBuf> {
         env::home_dir()
             .or_else(|| env::current_dir().ok())
@@ -65,7 +315,7 @@ impl Config {
             })
     }
 
-    fn get_dir(&self) -> Result<PathBuf, CommandError> {
+    fn get_dir() -> Result<PathBuf, CommandError> {
         let dir = match env::home_dir() {
             Some(d) => d,
             None => {
@@ -75,9 +325,8 @@ impl Config {
                     )
                 })?;
                 println!(
-                    "{} {}",
-                    "warning:".yellow().bold(),
-                    "Could not get home directory. Using local directory for user config."
+                    "{} Could not get home directory. Using local directory for user config.",
+                    "warning:".yellow().bold()
                 );
                 d
             }
@@ -95,39 +344,62 @@ impl Config {
         Ok(dir)
     }
 
-    fn filepath(&self) -> Result<PathBuf, CommandError> {
-        Ok(self.get_dir()?.join("config.json"))
-    }
-
+    /// Saves this config as its profile, leaving other profiles untouched. The
+    /// token goes to the credential store.
     pub fn store(&self) -> Result<(), CommandError> {
-        let filepath = self.filepath()?;
-        let f = fs::File::create(filepath)
-            .map_err(|_| CommandError::with_message("Could not open config file."))?;
-        serde_json::to_writer_pretty(f, self)
-            .map_err(|_| CommandError::with_message("Could not write to config file."))
+        if self.token_from_env {
+            return Ok(());
+        }
+
+        let mut config = self.clone();
+        if let Some(ref endpoint) = self.stored_endpoint {
+            config.endpoint = endpoint.clone();
+        }
+        let mut profiles = Profiles::load()?;
+        profiles.profiles.insert(self.profile.clone(), config);
+        profiles.store()?;
+
+        let store = credentials::store()?;
+        if self.token.is_empty() {
+            store.delete(&self.profile)
+        } else if store.get(&self.profile)?.as_ref() != Some(&self.token) {
+            store.set(&self.profile, &self.token)
+        } else {
+            Ok(())
+        }
     }
 
     pub fn clear(&mut self) -> Result<(), CommandError> {
         self.token = "".to_string();
+        self.token_type = TokenType::Session;
+        self.token_issued_at = None;
+        self.token_expires_at = None;
+        self.token_from_env = false;
+        self.store()
+    }
 
-        let filepath = self.filepath()?;
-        if filepath.exists() {
-            fs::remove_file(filepath)
-                .map_err(|_| CommandError::with_message("Failed to delete token."))?;
+    /// Whether a session token is far enough through its lifetime to be
+    /// refreshed: halfway, when both issue and expiry times are known.
+    pub fn token_needs_refresh(&self) -> bool {
+        if self.token.is_empty() || self.token_type != TokenType::Session {
+            return false;
         }
 
-        Ok(())
-    }
-
-    pub fn get_auth_header(&self) -> Authorization<Bearer> {
-        Authorization(Bearer {
-            token: self.token.to_owned(),
-        })
+        let refresh_at = match (self.token_issued_at, self.token_expires_at) {
+            (Some(iat), Some(exp)) => iat + (exp.signed_duration_since(iat) / 2),
+            (None, Some(exp)) => exp - Duration::hours(REFRESH_BEFORE_EXPIRY_HOURS),
+            (Some(iat), None) => iat + Duration::hours(REFRESH_THRESHOLD_HOURS),
+            (None, None) => return false,
+        };
+        refresh_at <= Utc::now()
     }
 
-    pub fn token_needs_refresh(&self) -> bool {
-        self.token != "" && self.token_issued_at.map_or(false, |iat| {
-            iat + Duration::hours(REFRESH_THRESHOLD_HOURS) <= Utc::now()
-        })
+    pub fn token_expired(&self) -> bool {
+        !self.token.is_empty()
+            && self.token_type == TokenType::Session
+            && self
+                .token_expires_at
+                .filter(|&exp| exp <= Utc::now())
+                .is_some()
     }
 }
@@ -0,0 +1,187 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use base64;
+use colored::*;
+use openssl::hash::MessageDigest;
+use openssl::pkcs5::pbkdf2_hmac;
+use openssl::symm::{decrypt_aead, encrypt_aead, Cipher};
+use rand::RngCore;
+use serde_json;
+
+use cli::prompt_password;
+use commands::generate::os_rng;
+use commands::CommandError;
+use credentials::CredentialStore;
+
+const ENCRYPTED_PREFIX: &str = "aes-256-gcm:";
+const PBKDF2_ITERATIONS: usize = 100_000;
+const SALT_LEN: usize = 16;
+const IV_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+lazy_static! {
+    // only ask for the passphrase once per run
+    static ref PASSPHRASE: Mutex<Option<String>> = Mutex::new(None);
+}
+
+/// Stores tokens in a JSON file that only the current user can read. When
+/// `encrypt` is set, tokens are encrypted with a key derived from a passphrase,
+/// taken from `DEPLOYC_PASSPHRASE` or prompted for.
+pub struct FileStore {
+    path: PathBuf,
+    encrypt: bool,
+}
+
+impl FileStore {
+    pub fn new(path: PathBuf, encrypt: bool) -> FileStore {
+        FileStore { path, encrypt }
+    }
+
+    fn read(&self) -> Result<BTreeMap<String, String>, CommandError> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+
+        let f = fs::File::open(&self.path)
+            .map_err(|_| CommandError::with_message("Could not open credentials file."))?;
+        serde_json::from_reader(f)
+            .map_err(|_| CommandError::with_message("Failed to read credentials file."))
+    }
+
+    fn write(&self, tokens: &BTreeMap<String, String>) -> Result<(), CommandError> {
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+            options.mode(0o600);
+            // the mode only applies to new files, so tighten up older ones too
+            if self.path.exists() {
+                fs::set_permissions(&self.path, fs::Permissions::from_mode(0o600)).map_err(
+                    |_| CommandError::with_message("Could not set credentials file permissions."),
+                )?;
+            }
+        }
+
+        let f = options
+            .open(&self.path)
+            .map_err(|_| CommandError::with_message("Could not open credentials file."))?;
+        serde_json::to_writer_pretty(f, tokens)
+            .map_err(|_| CommandError::with_message("Could not write to credentials file."))
+    }
+
+    fn passphrase(&self) -> Result<String, CommandError> {
+        let mut cached = PASSPHRASE.lock().unwrap();
+        if let Some(ref passphrase) = *cached {
+            return Ok(passphrase.clone());
+        }
+
+        let passphrase = match env::var("DEPLOYC_PASSPHRASE") {
+            Ok(passphrase) => passphrase,
+            Err(_) => prompt_password("Passphrase for stored credentials: ".bold())
+                .ok_or_else(|| CommandError::with_message("Invalid passphrase."))?,
+        };
+        if passphrase.is_empty() {
+            return Err(CommandError::with_message("Passphrase must not be empty."));
+        }
+
+        *cached = Some(passphrase.clone());
+        Ok(passphrase)
+    }
+
+    fn key(&self, salt: &[u8]) -> Result<[u8; 32], CommandError> {
+        let mut key = [0u8; 32];
+        pbkdf2_hmac(
+            self.passphrase()?.as_bytes(),
+            salt,
+            PBKDF2_ITERATIONS,
+            MessageDigest::sha256(),
+            &mut key,
+        ).map_err(|_| CommandError::with_message("Could not derive encryption key."))?;
+        Ok(key)
+    }
+
+    fn encrypt_token(&self, token: &str) -> Result<String, CommandError> {
+        let mut rng = os_rng()?;
+        let mut salt = [0u8; SALT_LEN];
+        let mut iv = [0u8; IV_LEN];
+        rng.fill_bytes(&mut salt);
+        rng.fill_bytes(&mut iv);
+
+        let key = self.key(&salt)?;
+        let mut tag = [0u8; TAG_LEN];
+        let data = encrypt_aead(
+            Cipher::aes_256_gcm(),
+            &key,
+            Some(&iv),
+            &[],
+            token.as_bytes(),
+            &mut tag,
+        ).map_err(|_| CommandError::with_message("Could not encrypt token."))?;
+
+        let mut blob = salt.to_vec();
+        blob.extend_from_slice(&iv);
+        blob.extend_from_slice(&tag);
+        blob.extend_from_slice(&data);
+        Ok(format!("{}{}", ENCRYPTED_PREFIX, base64::encode(&blob)))
+    }
+
+    fn decrypt_token(&self, value: &str) -> Result<String, CommandError> {
+        let invalid = || CommandError::with_message("Stored token is corrupt.");
+        let blob = base64::decode(&value[ENCRYPTED_PREFIX.len()..]).map_err(|_| invalid())?;
+        if blob.len() < SALT_LEN + IV_LEN + TAG_LEN {
+            return Err(invalid());
+        }
+        let (salt, rest) = blob.split_at(SALT_LEN);
+        let (iv, rest) = rest.split_at(IV_LEN);
+        let (tag, data) = rest.split_at(TAG_LEN);
+
+        let key = self.key(salt)?;
+        let token =
+            decrypt_aead(Cipher::aes_256_gcm(), &key, Some(iv), &[], data, tag).map_err(|_| {
+                CommandError::with_message_and_help(
+                    "Could not decrypt stored token.".to_string(),
+                    format!(
+                        "Check the passphrase, or delete {} and log in again.",
+                        self.path.display()
+                    ),
+                )
+            })?;
+        String::from_utf8(token).map_err(|_| invalid())
+    }
+}
+
+impl CredentialStore for FileStore {
+    fn get(&self, profile: &str) -> Result<Option<String>, CommandError> {
+        match self.read()?.remove(profile) {
+            Some(ref value) if value.starts_with(ENCRYPTED_PREFIX) => {
+                Ok(Some(self.decrypt_token(value)?))
+            }
+            value => Ok(value),
+        }
+    }
+
+    fn set(&self, profile: &str, token: &str) -> Result<(), CommandError> {
+        let value = if self.encrypt {
+            self.encrypt_token(token)?
+        } else {
+            token.to_string()
+        };
+
+        let mut tokens = self.read()?;
+        tokens.insert(profile.to_string(), value);
+        self.write(&tokens)
+    }
+
+    fn delete(&self, profile: &str) -> Result<(), CommandError> {
+        let mut tokens = self.read()?;
+        if tokens.remove(profile).is_some() {
+            self.write(&tokens)?;
+        }
+        Ok(())
+    }
+}
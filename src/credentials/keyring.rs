@@ -0,0 +1,112 @@
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use commands::CommandError;
+use credentials::CredentialStore;
+
+const SERVICE: &str = "deployc";
+
+/// Stores tokens with the Secret Service (GNOME Keyring, KWallet) through
+/// libsecret's `secret-tool`.
+pub struct Keyring;
+
+lazy_static! {
+    // looked up once, as the credential store is picked several times a command
+    static ref AVAILABLE: bool = cfg!(target_os = "linux")
+        && env::var_os("DBUS_SESSION_BUS_ADDRESS").is_some()
+        && has_secret_tool();
+}
+
+/// Whether `secret-tool` is on the `PATH`, without running it.
+#[cfg(unix)]
+fn has_secret_tool() -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = match env::var_os("PATH") {
+        Some(path) => path,
+        None => return false,
+    };
+    env::split_paths(&path).any(|dir| match fs::metadata(dir.join("secret-tool")) {
+        Ok(meta) => meta.is_file() && meta.permissions().mode() & 0o111 != 0,
+        Err(_) => false,
+    })
+}
+
+#[cfg(not(unix))]
+fn has_secret_tool() -> bool {
+    false
+}
+
+impl Keyring {
+    pub fn is_available() -> bool {
+        *AVAILABLE
+    }
+
+    fn command(action: &str, profile: &str) -> Command {
+        let mut cmd = Command::new("secret-tool");
+        cmd.arg(action);
+        if action == "store" {
+            cmd.arg("--label").arg(format!("deployc ({})", profile));
+        }
+        cmd.arg("service").arg(SERVICE).arg("profile").arg(profile);
+        cmd
+    }
+
+    fn error(err: &str) -> CommandError {
+        CommandError::with_message(format!("Could not access keyring: {}", err.trim()))
+    }
+}
+
+impl CredentialStore for Keyring {
+    fn get(&self, profile: &str) -> Result<Option<String>, CommandError> {
+        let output = Keyring::command("lookup", profile)
+            .output()
+            .map_err(|err| Keyring::error(&err.to_string()))?;
+
+        let token = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        match output.status.code() {
+            Some(0) if !token.is_empty() => Ok(Some(token)),
+            Some(0) => Ok(None),
+            // secret-tool exits with 1 and no output when nothing is stored
+            Some(1) if token.is_empty() && stderr.trim().is_empty() => Ok(None),
+            _ if !stderr.trim().is_empty() => Err(Keyring::error(&stderr)),
+            _ => Err(Keyring::error(&format!(
+                "secret-tool lookup failed ({})",
+                output.status
+            ))),
+        }
+    }
+
+    fn set(&self, profile: &str, token: &str) -> Result<(), CommandError> {
+        let mut child = Keyring::command("store", profile)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| Keyring::error(&err.to_string()))?;
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(token.as_bytes())
+            .map_err(|err| Keyring::error(&err.to_string()))?;
+
+        let output = child
+            .wait_with_output()
+            .map_err(|err| Keyring::error(&err.to_string()))?;
+        if !output.status.success() {
+            return Err(Keyring::error(&String::from_utf8_lossy(&output.stderr)));
+        }
+        Ok(())
+    }
+
+    fn delete(&self, profile: &str) -> Result<(), CommandError> {
+        Keyring::command("clear", profile)
+            .status()
+            .map_err(|err| Keyring::error(&err.to_string()))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use commands::CommandError;
+use credentials::CredentialStore;
+
+lazy_static! {
+    static ref TOKENS: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
+}
+
+/// Keeps tokens for the lifetime of the process only, for headless runs and
+/// tests where nothing should be persisted.
+pub struct MemoryStore;
+
+impl CredentialStore for MemoryStore {
+    fn get(&self, profile: &str) -> Result<Option<String>, CommandError> {
+        Ok(TOKENS.lock().unwrap().get(profile).cloned())
+    }
+
+    fn set(&self, profile: &str, token: &str) -> Result<(), CommandError> {
+        TOKENS
+            .lock()
+            .unwrap()
+            .insert(profile.to_string(), token.to_string());
+        Ok(())
+    }
+
+    fn delete(&self, profile: &str) -> Result<(), CommandError> {
+        TOKENS.lock().unwrap().remove(profile);
+        Ok(())
+    }
+}
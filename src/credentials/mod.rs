@@ -0,0 +1,46 @@
+use std::env;
+
+use commands::CommandError;
+use config::Config;
+
+mod file;
+mod keyring;
+mod memory;
+
+pub use self::file::FileStore;
+pub use self::keyring::Keyring;
+pub use self::memory::MemoryStore;
+
+/// Somewhere to keep tokens, keyed by profile name, so that they are not
+/// written to the config file in plain text.
+pub trait CredentialStore {
+    fn get(&self, profile: &str) -> Result<Option<String>, CommandError>;
+    fn set(&self, profile: &str, token: &str) -> Result<(), CommandError>;
+    fn delete(&self, profile: &str) -> Result<(), CommandError>;
+}
+
+/// Picks a credential store from `DEPLOYC_CREDENTIAL_STORE`, which can be
+/// `keyring`, `file`, `encrypted-file` or `memory`. By default the keyring is
+/// used when available, otherwise a file only readable by the current user.
+pub fn store() -> Result<Box<dyn CredentialStore>, CommandError> {
+    let path = Config::dir()
+        .ok_or_else(|| CommandError::with_message("Cannot access home or current directory."))?
+        .join("credentials.json");
+
+    let kind = env::var("DEPLOYC_CREDENTIAL_STORE").unwrap_or_default();
+    match kind.as_str() {
+        "" | "auto" if Keyring::is_available() => Ok(Box::new(Keyring)),
+        "" | "auto" | "file" => Ok(Box::new(FileStore::new(path, false))),
+        "encrypted-file" => Ok(Box::new(FileStore::new(path, true))),
+        "keyring" if Keyring::is_available() => Ok(Box::new(Keyring)),
+        "keyring" => Err(CommandError::with_message_and_help(
+            "The keyring is not available.",
+            "Install secret-tool and make sure a Secret Service provider is running.",
+        )),
+        "memory" => Ok(Box::new(MemoryStore)),
+        _ => Err(CommandError::with_message(format!(
+            "Invalid DEPLOYC_CREDENTIAL_STORE. Expected 'keyring', 'file', 'encrypted-file' or 'memory'. Found '{}'",
+            kind
+        ))),
+    }
+}
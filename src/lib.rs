@@ -0,0 +1,37 @@
+//! A client for the deployc.io API, and the app config (`deployc.json`) it
+//! deploys from. The `deployc` CLI is built on top of it.
+//!
+//! ```no_run
+//! extern crate deployc;
+//! extern crate url;
+//!
+//! use deployc::api::ApiClient;
+//! use url::Url;
+//!
+//! let client = ApiClient::new(Url::parse("https://deployc.io/api/").unwrap())
+//!     .with_token("token".to_string());
+//! for app in client.apps().unwrap_or_default() {
+//!     println!("{} ({})", app.name, app.tier);
+//! }
+//! ```
+
+// serde_derive 1.0 and hyper's `header!` implement traits inside a `const`
+#![allow(unknown_lints, non_local_definitions)]
+
+extern crate chrono;
+#[macro_use]
+extern crate hyper;
+extern crate openssl;
+extern crate rand;
+extern crate reqwest;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate serde_json;
+extern crate slug;
+extern crate url;
+
+pub mod api;
+pub mod app;
+pub mod models;
@@ -1,13 +1,19 @@
+// serde_derive 1.0 implements traits inside a `const`, and lazy_static 1.0
+// expands to the deprecated `ONCE_INIT`
+#![allow(unknown_lints, non_local_definitions, deprecated)]
+
 extern crate base64;
 extern crate chrono;
 extern crate chrono_humanize;
 #[macro_use]
 extern crate clap;
 extern crate colored;
-#[macro_use]
-extern crate hyper;
+extern crate deployc;
+extern crate ed25519_compact;
 #[macro_use]
 extern crate lazy_static;
+extern crate libflate;
+extern crate openssl;
 #[macro_use]
 extern crate prettytable;
 extern crate rand;
@@ -18,48 +24,114 @@ extern crate serde;
 extern crate serde_derive;
 #[macro_use]
 extern crate serde_json;
+extern crate serde_yaml;
 extern crate slug;
 extern crate tar;
+extern crate term;
 extern crate termion;
 extern crate url;
 extern crate url_serde;
 
-mod api;
-mod app;
 mod cli;
 mod commands;
 mod config;
-mod token_response;
+mod credentials;
+mod output;
+mod session;
+mod version;
 
 use std::path::PathBuf;
 
-use clap::{AppSettings, Arg, SubCommand};
+use clap::{AppSettings, Arg, ArgMatches, SubCommand};
 use colored::*;
+use deployc::app::App;
+use deployc::models::TokenResponse;
 
-use api::API;
-use app::App;
 use commands::{
     CommandError, CreateCommand, DeleteCommand, DescribeCommand, EnvCommand, ExposeCommand,
-    ListCommand, LoginCommand, LogoutCommand, LogsCommand, SecretsCommand, SignupCommand,
-    TierCommand, UpCommand,
+    ListCommand, LoginCommand, LogoutCommand, LogsCommand, ProfileCommand, SecretsCommand,
+    SessionsCommand, SignupCommand, TierCommand, TokensCommand, UpCommand, WhoamiCommand,
 };
 use config::Config;
-use token_response::TokenResponse;
 
 fn requires_login(cmd: &str) -> bool {
     match cmd {
-        "create" | "list" => true,
+        "create" | "list" | "sessions" | "tokens" => true,
         _ => requires_app(cmd),
     }
 }
 
 fn requires_app(cmd: &str) -> bool {
-    match cmd {
-        "delete" | "describe" | "env" | "expose" | "logs" | "secrets" | "tier" | "up" => true,
+    matches!(
+        cmd,
+        "delete" | "describe" | "env" | "expose" | "logs" | "secrets" | "tier" | "up"
+    )
+}
+
+/// Whether the command reads something that `--output` can format. `env`
+/// and `secrets` list when given no subcommand, and so does `tier`.
+fn takes_output(matches: &ArgMatches) -> bool {
+    match matches.subcommand() {
+        ("list", _) | ("describe", _) | ("logs", _) => true,
+        ("env", Some(m)) | ("secrets", Some(m)) => m.subcommand_name().unwrap_or("list") == "list",
+        ("tier", Some(m)) => m.subcommand_name().is_none(),
         _ => false,
     }
 }
 
+/// The command as typed, without its arguments, e.g. `env set`.
+fn command_name(matches: &ArgMatches) -> String {
+    let mut names = vec![];
+    let mut matches = matches;
+    while let (name, Some(m)) = matches.subcommand() {
+        names.push(name);
+        matches = m;
+    }
+    names.join(" ")
+}
+
+/// TLS settings of a profile, for `profile add` and `profile set`.
+fn tls_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("ca-bundle")
+            .help("PEM file of extra CA certificates to trust.")
+            .long("ca-bundle")
+            .value_name("FILE")
+            .takes_value(true),
+        Arg::with_name("client-cert")
+            .help("PEM file of a client certificate to present.")
+            .long("client-cert")
+            .value_name("FILE")
+            .requires("client-key")
+            .takes_value(true),
+        Arg::with_name("client-key")
+            .help("PEM file of the client certificate's private key.")
+            .long("client-key")
+            .value_name("FILE")
+            .requires("client-cert")
+            .takes_value(true),
+        Arg::with_name("skip-hostname-verification")
+            .help("Don't check the server certificate's hostname. The certificate must still be trusted. Not recommended.")
+            .long("skip-hostname-verification")
+            .takes_value(false),
+    ]
+}
+
+fn set_token(config: &mut Config, renewed: TokenResponse) {
+    config.token = renewed.token;
+    config.token_issued_at = renewed.issued_at;
+    config.token_expires_at = renewed.expires_at;
+}
+
+/// Stores a token renewed during the command, so the next command does not need to renew it.
+fn store_renewed_token(config: &mut Config) -> Result<(), CommandError> {
+    if let Some(renewed) = session::take_renewed() {
+        set_token(config, renewed);
+        config.store()?;
+    }
+    Ok(())
+}
+
 fn run() -> Result<(), CommandError> {
     let matches = clap::App::new("deployc")
         .version(crate_version!())
@@ -72,9 +144,184 @@ fn run() -> Result<(), CommandError> {
                 .short("a")
                 .required(false),
         )
-        .subcommand(SubCommand::with_name("login").about("Log in to deployc.io"))
+        .arg(
+            Arg::with_name("profile")
+                .help("Profile to use. Defaults to DEPLOYC_PROFILE, then the current profile.")
+                .long("profile")
+                .short("p")
+                .global(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("verbose")
+                .help("Log API requests to stderr, without credentials. Also set by DEPLOYC_DEBUG.")
+                .long("verbose")
+                .alias("debug")
+                .short("v")
+                .global(true)
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("timeout")
+                .help("Seconds to connect and wait for a response, 0 for no timeout. Defaults to 30.")
+                .long("timeout")
+                .value_name("SECS")
+                .global(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("retries")
+                .help("Times to retry requests that failed in passing. Defaults to 3.")
+                .long("retries")
+                .value_name("N")
+                .global(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("output")
+                .help("Output format of list, env list, secrets list, tier, describe and logs.")
+                .long("output")
+                .short("o")
+                .global(true)
+                .takes_value(true)
+                .possible_values(output::OUTPUT_FORMATS),
+        )
+        .subcommand(
+            SubCommand::with_name("login")
+                .about("Log in to deployc.io")
+                .arg(
+                    Arg::with_name("token-stdin")
+                        .help("Log in with an API token read from stdin.")
+                        .long("token-stdin")
+                        .conflicts_with_all(&["web", "otp"])
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("web")
+                        .help("Log in with a browser, e.g. for SSO.")
+                        .long("web")
+                        .conflicts_with("otp")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("no-browser")
+                        .help("Don't open the browser for --web, only print the URL.")
+                        .long("no-browser")
+                        .requires("web")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("otp")
+                        .help("Two-factor code. Prompted for when required and not given.")
+                        .long("otp")
+                        .takes_value(true),
+                ),
+        )
         .subcommand(SubCommand::with_name("logout").about("Log out from deployc.io"))
         .subcommand(SubCommand::with_name("signup").about("Sign up for deployc.io"))
+        .subcommand(SubCommand::with_name("whoami").about("Show the logged in account"))
+        .subcommand(
+            SubCommand::with_name("sessions")
+                .visible_alias("session")
+                .about("Manage login sessions on other devices")
+                .subcommand(SubCommand::with_name("list").visible_alias("ls"))
+                .subcommand(
+                    SubCommand::with_name("revoke")
+                        .about("Revoke a session.")
+                        .arg(
+                            Arg::with_name("all")
+                                .help("Revoke all sessions except the current one.")
+                                .long("all")
+                                .conflicts_with("id")
+                                .takes_value(false),
+                        )
+                        .arg(
+                            Arg::with_name("id")
+                                .help("Session ID.")
+                                .required_unless("all")
+                                .index(1),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("profile")
+                .visible_alias("profiles")
+                .about("Manage profiles for multiple accounts or endpoints")
+                .subcommand(
+                    SubCommand::with_name("add")
+                        .about("Add a profile.")
+                        .arg(
+                            Arg::with_name("endpoint")
+                                .help("URL of the deployc server. Defaults to DEPLOYC_ENDPOINT, then deployc.io.")
+                                .long("endpoint")
+                                .takes_value(true),
+                        )
+                        .args(&tls_args())
+                        .arg(
+                            Arg::with_name("use")
+                                .help("Switch to the profile after adding it.")
+                                .long("use")
+                                .takes_value(false),
+                        )
+                        .arg(
+                            Arg::with_name("name")
+                                .help("Profile name.")
+                                .required(true)
+                                .index(1),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("set")
+                        .about("Change the TLS settings of a profile.")
+                        .args(&tls_args())
+                        .arg(
+                            Arg::with_name("no-ca-bundle")
+                                .help("Only trust the system's CA certificates.")
+                                .long("no-ca-bundle")
+                                .conflicts_with("ca-bundle")
+                                .takes_value(false),
+                        )
+                        .arg(
+                            Arg::with_name("no-client-cert")
+                                .help("Don't present a client certificate.")
+                                .long("no-client-cert")
+                                .conflicts_with("client-cert")
+                                .takes_value(false),
+                        )
+                        .arg(
+                            Arg::with_name("verify-hostname")
+                                .help("Check the server certificate's hostname again.")
+                                .long("verify-hostname")
+                                .conflicts_with("skip-hostname-verification")
+                                .takes_value(false),
+                        )
+                        .arg(
+                            Arg::with_name("name")
+                                .help("Profile name. Defaults to the current profile.")
+                                .index(1),
+                        ),
+                )
+                .subcommand(SubCommand::with_name("list").visible_alias("ls"))
+                .subcommand(
+                    SubCommand::with_name("use").about("Switch profile.").arg(
+                        Arg::with_name("name")
+                            .help("Profile name.")
+                            .required(true)
+                            .index(1),
+                    ),
+                )
+                .subcommand(
+                    SubCommand::with_name("remove")
+                        .visible_alias("rm")
+                        .about("Remove a profile.")
+                        .arg(
+                            Arg::with_name("name")
+                                .help("Profile name.")
+                                .required(true)
+                                .index(1),
+                        ),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("create")
                 .about("Creates an app")
@@ -85,6 +332,47 @@ fn run() -> Result<(), CommandError> {
                 .visible_aliases(&["ls", "apps"])
                 .about("Lists apps"),
         )
+        .subcommand(
+            SubCommand::with_name("tokens")
+                .visible_alias("token")
+                .about("Manage API tokens")
+                .subcommand(
+                    SubCommand::with_name("create")
+                        .about("Create a long-lived API token, e.g. for CI.")
+                        .arg(
+                            Arg::with_name("scope")
+                                .help("Scope granted to the token. May be repeated.")
+                                .long("scope")
+                                .short("s")
+                                .possible_values(&["read", "write", "deploy"])
+                                .multiple(true)
+                                .number_of_values(1)
+                                .required(true)
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("expires-in")
+                                .help("Expire the token after number of days. Defaults to never.")
+                                .long("expires-in")
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("name")
+                                .help("Token name.")
+                                .required(true)
+                                .index(1),
+                        ),
+                )
+                .subcommand(SubCommand::with_name("list").visible_alias("ls"))
+                .subcommand(
+                    SubCommand::with_name("revoke").about("Revoke an API token.").arg(
+                        Arg::with_name("name")
+                            .help("Token name.")
+                            .required(true)
+                            .index(1),
+                    ),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("expose")
                 .visible_alias("port")
@@ -129,13 +417,33 @@ fn run() -> Result<(), CommandError> {
                 .subcommand(
                     SubCommand::with_name("create")
                         .arg(
-                            Arg::with_name("bytes")
-                                .help("Randomly generate a secret with number of bytes.")
-                                .long("generate")
+                            Arg::with_name("length")
+                                .help("Randomly generate a secret with number of characters.")
+                                .long("length")
+                                .visible_alias("generate")
                                 .short("g")
                                 .required(false)
                                 .takes_value(true),
                         )
+                        .arg(
+                            Arg::with_name("bytes")
+                                .help("Randomly generate a secret from number of random bytes.")
+                                .long("bytes")
+                                .conflicts_with("length")
+                                .required(false)
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("charset")
+                                .help(
+                                    "Characters of generated secret: alnum, hex, base64, base64url, \
+                                     ascii-printable, or custom:<chars>.",
+                                )
+                                .long("charset")
+                                .required(false)
+                                .takes_value(true)
+                                .default_value("alnum"),
+                        )
                         .arg(
                             Arg::with_name("type")
                                 .help("Type of secret.")
@@ -143,20 +451,38 @@ fn run() -> Result<(), CommandError> {
                                 .short("t")
                                 .required(false)
                                 .takes_value(true)
-                                .possible_values(&["raw", "certificate", "credentials"])
+                                .possible_values(&[
+                                    "raw",
+                                    "certificate",
+                                    "credentials",
+                                    "ssh-key",
+                                    "registry",
+                                    "json",
+                                ])
                                 .default_value("raw"),
                         )
                         .arg(
                             Arg::with_name("file")
-                                .help("Use value from file.")
+                                .help("Use value from file. Use '-' to read from stdin.")
                                 .long("file")
                                 .short("f")
                                 .conflicts_with("key")
+                                .required(false)
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("stdin")
+                                .help(
+                                    "Read value from stdin: a raw or JSON value, a registry password \
+                                     or an SSH private key.",
+                                )
+                                .long("stdin")
+                                .conflicts_with_all(&["file", "key", "length", "bytes"])
                                 .required(false),
                         )
                         .arg(
                             Arg::with_name("key")
-                                .help("Use private key from file. For certificates only.")
+                                .help("Use private key from file. For certificates and SSH keys.")
                                 .long("key")
                                 .short("k")
                                 .required_if("type", "certificate")
@@ -170,6 +496,34 @@ fn run() -> Result<(), CommandError> {
                                 .required_if("type", "certificate")
                                 .takes_value(true),
                         )
+                        .arg(
+                            Arg::with_name("keygen")
+                                .help("Generate an ed25519 keypair. For SSH keys only.")
+                                .long("keygen")
+                                .conflicts_with_all(&["key", "stdin"])
+                                .required(false),
+                        )
+                        .arg(
+                            Arg::with_name("server")
+                                .help("Registry server, e.g. registry.example.com. For registries only.")
+                                .long("server")
+                                .required_if("type", "registry")
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("username")
+                                .help("Registry username. For registries only.")
+                                .long("username")
+                                .short("u")
+                                .required(false)
+                                .takes_value(true),
+                        )
+                        .arg(
+                            Arg::with_name("force")
+                                .help("Upload certificate even if it fails validation.")
+                                .long("force")
+                                .required(false),
+                        )
                         .arg(
                             Arg::with_name("name")
                                 .help("Name of secret.")
@@ -210,32 +564,149 @@ fn run() -> Result<(), CommandError> {
                         .help("Tail number of lines.")
                         .long("tail")
                         .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("since")
+                        .help("Show logs since a duration ago (e.g. 1h) or a time (e.g. 2018-06-01T10:00).")
+                        .long("since")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("until")
+                        .help("Show logs until a duration ago (e.g. 30m) or a time.")
+                        .long("until")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("service")
+                        .help("Only show logs from service.")
+                        .long("service")
+                        .short("s")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("grep")
+                        .help("Only show lines matching regex.")
+                        .long("grep")
+                        .short("g")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("level")
+                        .help("Hide JSON log lines below level. Lines without a level are always shown.")
+                        .long("level")
+                        .short("l")
+                        .takes_value(true)
+                        .possible_values(&["trace", "debug", "info", "warn", "error", "fatal"]),
+                )
+                .arg(
+                    Arg::with_name("timestamps")
+                        .help("How to show timestamps.")
+                        .long("timestamps")
+                        .takes_value(true)
+                        .possible_values(&["local", "utc", "relative", "raw", "none"])
+                        .default_value("local"),
+                )
+                .arg(
+                    Arg::with_name("time-format")
+                        .help("strftime format for local and utc timestamps.")
+                        .long("time-format")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("no-prefix")
+                        .help("Do not show the source of each line.")
+                        .long("no-prefix")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("save")
+                        .help("Also write every received line to file.")
+                        .long("save")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("max-size")
+                        .help("Rotate the saved file once it reaches size (e.g. 500K). Defaults to 10M.")
+                        .long("max-size")
+                        .requires("save")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("keep")
+                        .help("Number of rotated files to keep. Defaults to 5.")
+                        .long("keep")
+                        .requires("save")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("compress")
+                        .help("Gzip rotated files.")
+                        .long("compress")
+                        .requires("save")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("invert")
+                        .help("Only show lines not matching --grep.")
+                        .long("invert")
+                        .requires("grep")
+                        .takes_value(false),
                 ),
         )
         .get_matches();
 
+    // --output is global so it can go anywhere, but only read commands use it
+    if matches.is_present("output") && !takes_output(&matches) {
+        return Err(CommandError::with_message_and_help(
+            format!("{} has no output format.", command_name(&matches).bold()),
+            format!(
+                "{} works with list, env list, secrets list, tier, describe and logs.",
+                "--output".bold()
+            ),
+        ));
+    }
+
+    // profiles are managed directly in the config file
+    if let ("profile", Some(m)) = matches.subcommand() {
+        return ProfileCommand::run(m);
+    }
+
     // get config
-    let mut config = Config::get()?;
+    let mut config = Config::get(matches.value_of("profile"))?;
+    if let Some(timeout) = matches.value_of("timeout") {
+        config.request_options.read_timeout = config::parse_timeout(timeout)?;
+    }
+    if let Some(retries) = matches.value_of("retries") {
+        config.request_options.retries = config::parse_retries(retries)?;
+    }
+    if matches.is_present("verbose") {
+        config.request_options.verbose = true;
+    }
+    if config.skip_hostname_verification {
+        eprintln!(
+            "{} TLS hostname verification is off for profile {} (skipHostnameVerification). \
+             Anyone on the network path could impersonate the server.",
+            "warning:".yellow().bold(),
+            config.profile.bold()
+        );
+    }
 
     // run subcommands
     let (subcmd, maybe_submatches) = matches.subcommand();
     if requires_login(subcmd) {
-        if config.token == "" {
+        if config.token.is_empty() {
             return Err(CommandError::with_message_and_help(
                 format!("Not logged in. Log in required for {}", subcmd.bold()),
                 format!("{} {}", "Run".dimmed(), "deployc login".bold()),
             ));
+        } else if config.token_expired() {
+            session::renew(&config)?;
+            store_renewed_token(&mut config)?;
         } else if config.token_needs_refresh() {
-            // Note: fail silently here as to not scare the user. Might want to change this in the future (?)
-            if let Ok(TokenResponse {
-                token,
-                issued_at,
-                expires_at,
-            }) = API::new(&config).refresh().get()
-            {
-                config.token = token;
-                config.token_issued_at = issued_at;
-                config.token_expires_at = expires_at;
+            // Note: fail silently here, the token is still valid. Requests renew it if it is rejected.
+            if let Ok(renewed) = config.client().refresh() {
+                set_token(&mut config, renewed);
             }
         }
     }
@@ -257,24 +728,31 @@ fn run() -> Result<(), CommandError> {
         None
     };
 
+    // one client for the whole command, so its requests share connections
+    let client = config.client();
     let result = match (subcmd, maybe_submatches) {
-        ("login", _) => LoginCommand::run(&mut config),
-        ("logout", _) => LogoutCommand::run(&mut config),
-        ("signup", _) => SignupCommand::run(&mut config),
-        ("list", _) => ListCommand::run(&config),
-        ("create", Some(m)) => CreateCommand::run(m, &config),
-        ("up", Some(m)) => UpCommand::run(m, &config, &app.unwrap()),
-        ("describe", _) => DescribeCommand::run(&app.unwrap()),
-        ("env", Some(m)) => EnvCommand::run(m, &config, &app.unwrap()),
-        ("secrets", Some(m)) => SecretsCommand::run(m, &config, &app.unwrap()),
+        ("login", Some(m)) => LoginCommand::run(m, &mut config, &client),
+        ("logout", _) => LogoutCommand::run(&mut config, &client),
+        ("signup", _) => SignupCommand::run(&mut config, &client),
+        ("whoami", _) => WhoamiCommand::run(&config, &client),
+        ("list", Some(m)) => ListCommand::run(m, &client),
+        ("create", Some(m)) => CreateCommand::run(m, &client),
+        ("up", Some(m)) => UpCommand::run(m, &client, &app.unwrap()),
+        ("describe", Some(m)) => DescribeCommand::run(m, &app.unwrap()),
+        ("env", Some(m)) => EnvCommand::run(m, &client, &app.unwrap()),
+        ("secrets", Some(m)) => SecretsCommand::run(m, &client, &app.unwrap()),
         ("expose", Some(m)) => ExposeCommand::run(m, &mut app.unwrap()),
-        ("delete", Some(m)) => DeleteCommand::run(m, &config, &app.unwrap()),
-        ("logs", Some(m)) => LogsCommand::run(m, &config, &app.unwrap()),
-        ("tier", Some(m)) => TierCommand::run(m, &config, &app.unwrap()),
+        ("delete", Some(m)) => DeleteCommand::run(m, &client, &app.unwrap()),
+        ("logs", Some(m)) => LogsCommand::run(m, &client, &app.unwrap()),
+        ("tier", Some(m)) => TierCommand::run(m, &client, &app.unwrap()),
+        ("tokens", Some(m)) => TokensCommand::run(m, &client),
+        ("sessions", Some(m)) => SessionsCommand::run(m, &client),
         _ => Ok(()),
     };
+    version::warn_if_deprecated(&client);
 
-    if config.token != "" {
+    store_renewed_token(&mut config)?;
+    if !config.token.is_empty() {
         config.store()?;
     }
 
@@ -282,10 +760,16 @@ fn run() -> Result<(), CommandError> {
 }
 
 fn main() {
+    output::init_colors();
     if let Err(e) = run() {
-        println!("{} {}", "error:".bold().red(), e.message);
-        if e.help != "" {
-            println!("{} {}", "help:".bold().blue(), e.help);
+        // on stderr, so they don't end up in output piped to another program
+        eprintln!("{} {}", "error:".bold().red(), e.message);
+        if !e.help.is_empty() {
+            eprintln!("{} {}", "help:".bold().blue(), e.help);
+        }
+        if let Some(id) = e.request_id {
+            eprintln!("{} {}", "request id:".dimmed(), id);
         }
+        std::process::exit(e.code);
     }
 }
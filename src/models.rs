@@ -0,0 +1,102 @@
+//! Resources returned by the API, other than apps and their secrets and
+//! environment, which are in [`app`](../app/index.html).
+
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+
+/// The account the client is logged in as.
+#[derive(Serialize, Deserialize)]
+pub struct User {
+    pub username: String,
+    pub email: String,
+}
+
+/// An account created by signing up, along with its first session token.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Signup {
+    pub id: String,
+    pub username: String,
+    pub email: String,
+    pub token: String,
+    pub token_issued_at: Option<DateTime<Utc>>,
+    pub token_expires_at: Option<DateTime<Utc>>,
+}
+
+/// A session token from logging in or refreshing.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenResponse {
+    pub token: String,
+    pub issued_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// A code for logging in with the OAuth device authorization flow (RFC 8628).
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceCode {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub verification_uri_complete: Option<String>,
+    pub expires_in: u64,
+    pub interval: Option<u64>,
+}
+
+/// A login session, on this device or another.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    pub id: String,
+    pub device: Option<String>,
+    pub ip: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// Whether this is the session the client is using.
+    #[serde(default)]
+    pub current: bool,
+}
+
+/// A long-lived API token. Its secret is only given when it is created.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiToken {
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CreatedToken {
+    pub token: ApiToken,
+    pub secret: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CreatedApp {
+    pub name: String,
+    pub tier: String,
+}
+
+/// A pricing tier for apps.
+#[derive(Serialize, Deserialize)]
+pub struct Tier {
+    pub name: String,
+    pub pricing: String,
+}
+
+impl fmt::Display for Tier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.pricing)
+    }
+}
+
+impl PartialEq for Tier {
+    fn eq(&self, other: &Tier) -> bool {
+        self.name == other.name
+    }
+}
@@ -0,0 +1,150 @@
+use std::env;
+use std::io::{self, stdout, Write};
+use std::str::FromStr;
+
+use clap::ArgMatches;
+use colored;
+use colored::*;
+use prettytable::cell::Cell;
+use prettytable::row::Row;
+use serde::Serialize;
+use serde_json;
+use serde_yaml;
+use termion;
+
+use cli::{output_written, print_table};
+use commands::CommandError;
+
+/// Values of `--output`. `text` is what `logs` called its default format.
+pub const OUTPUT_FORMATS: &[&str] = &["table", "wide", "json", "yaml", "name", "text"];
+
+/// How a read command shows its result: a table for people, or JSON, YAML
+/// or bare names for scripts.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Output {
+    Table,
+    /// A table with extra columns, for wide terminals.
+    Wide,
+    Json,
+    Yaml,
+    /// One name per line, e.g. to pipe into `xargs`.
+    Name,
+}
+
+impl FromStr for Output {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Output, String> {
+        match s {
+            "table" | "text" => Ok(Output::Table),
+            "wide" => Ok(Output::Wide),
+            "json" => Ok(Output::Json),
+            "yaml" => Ok(Output::Yaml),
+            "name" => Ok(Output::Name),
+            _ => Err(format!(
+                "Invalid output format: {}. Expected one of: {}.",
+                s,
+                OUTPUT_FORMATS.join(", ")
+            )),
+        }
+    }
+}
+
+impl Output {
+    /// The format given with `--output`, or `default`.
+    pub fn from_matches(matches: &ArgMatches, default: Output) -> Result<Output, CommandError> {
+        match matches.value_of("output") {
+            Some(output) => output.parse().map_err(CommandError::with_message),
+            None => Ok(default),
+        }
+    }
+}
+
+/// What a read command shows, as a table row or serialized as is.
+pub trait View: Serialize {
+    /// Column titles. `wide` adds columns for `--output wide`.
+    fn titles(wide: bool) -> Vec<&'static str>;
+
+    /// One cell per title.
+    fn cells(&self, wide: bool) -> Vec<String>;
+
+    /// What `--output name` prints.
+    fn name(&self) -> &str;
+}
+
+/// Leaves out colors when stdout is not a terminal, unless `CLICOLOR_FORCE`
+/// asks for them: `colored` only looks at the environment.
+pub fn init_colors() {
+    if !termion::is_tty(&io::stdout()) && env::var_os("CLICOLOR_FORCE").is_none() {
+        colored::control::set_override(false);
+    }
+}
+
+fn serialize<T: Serialize + ?Sized>(output: Output, value: &T) -> Result<(), CommandError> {
+    let s = match output {
+        Output::Yaml => serde_yaml::to_string(value).map_err(|err| err.to_string()),
+        _ => serde_json::to_string_pretty(value).map_err(|err| err.to_string()),
+    };
+    match s {
+        Ok(s) => output_written(writeln!(stdout(), "{}", s)),
+        Err(err) => Err(CommandError::with_message(format!(
+            "Could not write output: {}",
+            err
+        ))),
+    }
+}
+
+/// Prints a list of views. A table shows `empty` instead when there are none;
+/// other formats print an empty list.
+pub fn print_list<V: View>(output: Output, views: &[V], empty: &str) -> Result<(), CommandError> {
+    match output {
+        Output::Table | Output::Wide if views.is_empty() => {
+            output_written(writeln!(stdout(), "{}", empty))
+        }
+        Output::Table | Output::Wide => {
+            let wide = output == Output::Wide;
+            let titles = V::titles(wide)
+                .into_iter()
+                .map(|title| Cell::new(title).style_spec("Fbb"))
+                .collect();
+            print_table(
+                Row::new(titles),
+                views
+                    .iter()
+                    .map(|view| Row::from(view.cells(wide)))
+                    .collect(),
+            )
+        }
+        Output::Name => {
+            let stdout = stdout();
+            let mut out = stdout.lock();
+            output_written(
+                views
+                    .iter()
+                    .try_for_each(|view| writeln!(out, "{}", view.name())),
+            )
+        }
+        Output::Json | Output::Yaml => serialize(output, views),
+    }
+}
+
+/// Prints a single view. A table shows one field per line.
+pub fn print_one<V: View>(output: Output, view: &V) -> Result<(), CommandError> {
+    match output {
+        Output::Table | Output::Wide => {
+            let wide = output == Output::Wide;
+            let stdout = stdout();
+            let mut out = stdout.lock();
+            output_written(
+                V::titles(wide)
+                    .into_iter()
+                    .zip(view.cells(wide))
+                    .try_for_each(|(title, cell)| {
+                        writeln!(out, "{} {}", format!("{}:", title).bold(), cell)
+                    }),
+            )
+        }
+        Output::Name => output_written(writeln!(stdout(), "{}", view.name())),
+        Output::Json | Output::Yaml => serialize(output, view),
+    }
+}
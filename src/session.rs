@@ -0,0 +1,59 @@
+use std::sync::Mutex;
+
+use colored::*;
+use deployc::models::TokenResponse;
+
+use cli::{is_interactive, prompt_credentials};
+use commands::{CommandError, LoginCommand, EXIT_AUTH};
+use config::{Config, TokenType};
+
+lazy_static! {
+    // a token renewed part way through a command, to be used by later requests and stored
+    static ref RENEWED: Mutex<Option<TokenResponse>> = Mutex::new(None);
+}
+
+/// The token requests should be made with, taking any renewal into account.
+pub fn current_token(config: &Config) -> String {
+    match *RENEWED.lock().unwrap() {
+        Some(ref renewed) => renewed.token.clone(),
+        None => config.token.clone(),
+    }
+}
+
+/// Takes the renewed token, if any, so it can be stored in the config.
+pub fn take_renewed() -> Option<TokenResponse> {
+    RENEWED.lock().unwrap().take()
+}
+
+/// Only login sessions can be renewed; API tokens are managed by the user.
+pub fn can_renew(config: &Config) -> bool {
+    !config.token.is_empty() && config.token_type == TokenType::Session && !config.token_from_env
+}
+
+fn expired() -> CommandError {
+    CommandError::with_message_and_help(
+        "Session expired.".to_string(),
+        format!("{} {}", "Run".dimmed(), "deployc login".bold()),
+    ).with_code(EXIT_AUTH)
+}
+
+/// Gets a new token for an expired or rejected session: by refreshing if the
+/// server still allows it, otherwise by asking the user to log in again.
+pub fn renew(config: &Config) -> Result<(), CommandError> {
+    let client = config.client();
+    let renewed = match client.refresh() {
+        Ok(renewed) => renewed,
+        Err(_) if !is_interactive() => return Err(expired()),
+        Err(_) => {
+            println!(
+                "{} Session expired. Log in again to continue.",
+                "note:".cyan().bold()
+            );
+            let (username, password) = prompt_credentials()?;
+            LoginCommand::login(&client, &username, &password, None)?
+        }
+    };
+
+    *RENEWED.lock().unwrap() = Some(renewed);
+    Ok(())
+}
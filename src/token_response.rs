@@ -1,9 +0,0 @@
-use chrono::{DateTime, Utc};
-
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct TokenResponse {
-    pub token: String,
-    pub issued_at: Option<DateTime<Utc>>,
-    pub expires_at: Option<DateTime<Utc>>,
-}
@@ -0,0 +1,66 @@
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Duration, Utc};
+use colored::*;
+use deployc::api::{ApiClient, Compatibility, API_VERSION};
+use serde_json;
+
+use config::Config;
+
+// how often to remind the user that their deployc is out of date
+const WARNING_INTERVAL_HOURS: i64 = 24;
+
+/// When the user was last warned that an API version is deprecated.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeprecationWarning {
+    api_version: String,
+    warned_at: DateTime<Utc>,
+}
+
+fn filepath() -> Option<PathBuf> {
+    Config::dir().map(|dir| dir.join("version-check.json"))
+}
+
+fn warned_recently(path: &PathBuf) -> bool {
+    fs::File::open(path)
+        .ok()
+        .and_then(|f| serde_json::from_reader::<_, DeprecationWarning>(f).ok())
+        .filter(|last| {
+            last.api_version == API_VERSION
+                && last.warned_at + Duration::hours(WARNING_INTERVAL_HOURS) > Utc::now()
+        })
+        .is_some()
+}
+
+/// Warns when the server has deprecated the API version this deployc speaks,
+/// at most once a day.
+pub fn warn_if_deprecated(client: &ApiClient) {
+    let deprecated = client
+        .server_version()
+        .filter(|server| server.compatibility(API_VERSION) == Compatibility::Deprecated)
+        .is_some();
+    let path = match filepath() {
+        Some(path) => path,
+        None => return,
+    };
+    if !deprecated || warned_recently(&path) {
+        return;
+    }
+
+    eprintln!(
+        "{} This version of deployc uses API version {}, which the server has deprecated. \
+         Upgrade deployc before it stops working.",
+        "warning:".yellow().bold(),
+        API_VERSION
+    );
+    let warning = DeprecationWarning {
+        api_version: API_VERSION.to_string(),
+        warned_at: Utc::now(),
+    };
+    // failing to record the warning only means it is shown again next time
+    if let Ok(f) = fs::File::create(path) {
+        let _ = serde_json::to_writer(f, &warning);
+    }
+}
@@ -0,0 +1,294 @@
+//! Logging in, signing up and keeping the session fresh.
+
+extern crate chrono;
+#[macro_use]
+extern crate serde_json;
+
+mod support;
+
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::{Duration, Utc};
+
+use support::*;
+
+#[test]
+fn login_stores_session_in_credential_store() {
+    let env = TestEnv::new();
+    env.run_with_input(&["login"], &format!("{}\n{}\n", USERNAME, PASSWORD))
+        .assert_success()
+        .assert_stdout("Logged in!");
+
+    let req = env.server.last_request("POST", "/api/login");
+    assert_eq!(req.json()["username"], USERNAME);
+    assert_eq!(req.json()["password"], PASSWORD);
+
+    let config = env.config();
+    assert_eq!(config["endpoint"], format!("{}api/", env.server.url));
+    assert_eq!(config["tokenType"], "session");
+    assert!(config["tokenIssuedAt"].is_string());
+    assert!(config["tokenExpiresAt"].is_string());
+    // the token is kept out of the config file
+    assert!(config.get("token").is_none());
+    assert_eq!(env.token().as_deref(), Some(SESSION_TOKEN));
+}
+
+#[test]
+fn login_with_wrong_password() {
+    let env = TestEnv::new();
+    env.run_with_input(&["login"], &format!("{}\nwrong\n", USERNAME))
+        .assert_failure(77)
+        .assert_stderr("Invalid username or password.");
+    assert_eq!(env.token(), None);
+}
+
+#[test]
+fn login_with_two_factor_code() {
+    let env = TestEnv::new();
+    let credentials = format!("{}\n{}\n", OTP_USERNAME, PASSWORD);
+    env.run_with_input(&["login"], &credentials)
+        .assert_failure(1)
+        .assert_stderr("Two-factor code required.")
+        .assert_stderr("--otp <code>");
+
+    env.run_with_input(&["login", "--otp", OTP], &credentials)
+        .assert_success();
+    assert_eq!(
+        env.server.last_request("POST", "/api/login").json()["otp"],
+        OTP
+    );
+    assert_eq!(env.token().as_deref(), Some(SESSION_TOKEN));
+}
+
+#[test]
+fn login_with_api_token() {
+    let env = TestEnv::new();
+    env.run_with_input(&["login", "--token-stdin"], &format!("{}\n", API_TOKEN))
+        .assert_success()
+        .assert_stdout(&format!("Using API token {}.", API_TOKEN_NAME));
+
+    let config = env.config();
+    assert_eq!(config["tokenType"], "api");
+    assert!(config["tokenExpiresAt"].is_null());
+    assert_eq!(env.token().as_deref(), Some(API_TOKEN));
+}
+
+#[test]
+fn login_warns_of_deprecated_api_version() {
+    let env = TestEnv::new();
+    env.server
+        .add_header("X-API-Supported-Versions", "2018-06-01");
+    env.server
+        .add_header("X-API-Deprecated-Versions", "2018-06-01");
+    env.run_with_input(&["login"], &format!("{}\n{}\n", USERNAME, PASSWORD))
+        .assert_success()
+        .assert_stderr("which the server has deprecated");
+}
+
+#[test]
+fn unsupported_api_version() {
+    let env = TestEnv::new();
+    env.server
+        .add_header("X-API-Supported-Versions", "2019-01-01");
+    env.run_with_input(&["login"], &format!("{}\n{}\n", USERNAME, PASSWORD))
+        .assert_failure(79);
+    assert_eq!(env.token(), None);
+}
+
+#[test]
+fn login_with_invalid_api_token() {
+    let env = TestEnv::new();
+    env.run_with_input(&["login", "--token-stdin"], "nope\n")
+        .assert_failure(77);
+    assert_eq!(env.token(), None);
+}
+
+#[test]
+fn signup_logs_in() {
+    let env = TestEnv::new();
+    env.run_with_input(&["signup"], "carol\ncarol@example.com\nhunter2\n")
+        .assert_success()
+        .assert_stdout("You are now signed up for deployc.io!");
+
+    let req = env.server.last_request("POST", "/api/signup");
+    assert_eq!(
+        req.json(),
+        json!({ "username": "carol", "email": "carol@example.com", "password": "hunter2" })
+    );
+    assert!(env.config()["tokenExpiresAt"].is_string());
+    assert_eq!(env.token().as_deref(), Some(SESSION_TOKEN));
+}
+
+#[test]
+fn signup_with_taken_username() {
+    let env = TestEnv::new();
+    env.run_with_input(
+        &["signup"],
+        &format!("{}\ncarol@example.com\nhunter2\n", TAKEN_USERNAME),
+    )
+    .assert_failure(65)
+    .assert_stderr("username: is already taken");
+    assert_eq!(env.token(), None);
+}
+
+#[test]
+fn signup_with_invalid_email() {
+    let env = TestEnv::new();
+    env.run_with_input(&["signup"], "carol\nnot-an-email\nhunter2\n")
+        .assert_failure(1)
+        .assert_stderr("Invalid email.");
+    assert!(env.server.requests().is_empty());
+}
+
+#[test]
+fn commands_require_login() {
+    let env = TestEnv::new();
+    env.run(&["list"])
+        .assert_failure(1)
+        .assert_stderr("Not logged in.");
+    assert!(env.server.requests().is_empty());
+}
+
+#[test]
+fn session_is_refreshed_halfway_through() {
+    let env = TestEnv::new();
+    let issued_at = Utc::now() - Duration::days(5);
+    env.write_config(
+        json!({
+            "endpoint": format!("{}api/", env.server.url),
+            "tokenType": "session",
+            "tokenIssuedAt": issued_at,
+            "tokenExpiresAt": issued_at + Duration::days(7)
+        }),
+        SESSION_TOKEN,
+    );
+
+    env.run(&["list"]).assert_success();
+    let refresh = env.server.last_request("GET", "/api/refresh");
+    assert_eq!(
+        refresh.header("authorization"),
+        Some(format!("Bearer {}", SESSION_TOKEN).as_str())
+    );
+    // the command itself uses the new token
+    let list = env.server.last_request("GET", "/api/apps/");
+    assert_eq!(
+        list.header("authorization"),
+        Some(format!("Bearer {}", REFRESHED_TOKEN).as_str())
+    );
+    assert_eq!(env.token().as_deref(), Some(REFRESHED_TOKEN));
+    assert!(env.config()["tokenIssuedAt"].as_str() > Some(issued_at.to_rfc3339().as_str()));
+}
+
+#[test]
+fn api_tokens_are_not_refreshed() {
+    let env = TestEnv::new();
+    env.write_config(
+        json!({
+            "endpoint": format!("{}api/", env.server.url),
+            "tokenType": "api",
+            "tokenIssuedAt": Utc::now() - Duration::days(30),
+            "tokenExpiresAt": null
+        }),
+        API_TOKEN,
+    );
+
+    env.run(&["list"]).assert_success();
+    assert!(env
+        .server
+        .requests()
+        .iter()
+        .all(|req| req.path != "/api/refresh"));
+    assert_eq!(env.token().as_deref(), Some(API_TOKEN));
+}
+
+#[test]
+fn logout_keeps_login_while_token_from_env() {
+    let env = TestEnv::new();
+    env.login();
+
+    env.run_with_env(&["logout"], &[("DEPLOYC_TOKEN", API_TOKEN)])
+        .assert_failure(1)
+        .assert_stderr("Logged in with DEPLOYC_TOKEN.")
+        .assert_stderr("Unset DEPLOYC_TOKEN to log out.");
+    assert!(env
+        .server
+        .requests()
+        .iter()
+        .all(|req| req.path != "/api/logout"));
+    assert_eq!(env.token().as_deref(), Some(SESSION_TOKEN));
+
+    env.run(&["list"]).assert_success();
+}
+
+/// Installs a `secret-tool` that runs `script`, in a directory to use as `PATH`.
+#[cfg(unix)]
+fn fake_secret_tool(env: &TestEnv, script: &str) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let bin = env.home.join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    let path = bin.join("secret-tool");
+    fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    bin
+}
+
+#[cfg(unix)]
+#[test]
+fn keyring_lookup_failures() {
+    let env = TestEnv::new();
+    let list = |script: &str| {
+        let bin = fake_secret_tool(&env, script);
+        env.run_with_env(
+            &["list"],
+            &[
+                ("PATH", bin.to_str().unwrap()),
+                ("DBUS_SESSION_BUS_ADDRESS", "unix:path=/dev/null"),
+                ("DEPLOYC_CREDENTIAL_STORE", "keyring"),
+            ],
+        )
+    };
+
+    // nothing stored
+    list("exit 1")
+        .assert_failure(1)
+        .assert_stderr("Not logged in.");
+    list("echo Cannot autolaunch D-Bus >&2; exit 1")
+        .assert_failure(1)
+        .assert_stderr("Could not access keyring: Cannot autolaunch D-Bus");
+    list("exit 2")
+        .assert_failure(1)
+        .assert_stderr("Could not access keyring: secret-tool lookup failed");
+    list(&format!("echo {}", SESSION_TOKEN)).assert_success();
+}
+
+#[test]
+fn rejected_token() {
+    let env = TestEnv::new();
+    env.login();
+    env.server.state().tokens.clear();
+
+    // the session can't be refreshed, and there is no one to log in again
+    env.run(&["list"])
+        .assert_failure(77)
+        .assert_stderr("Session expired.")
+        .assert_stderr("deployc login");
+}
+
+#[test]
+fn rejected_api_token() {
+    let env = TestEnv::new();
+    env.run_with_input(&["login", "--token-stdin"], &format!("{}\n", API_TOKEN))
+        .assert_success();
+    env.server.state().tokens.clear();
+
+    env.run(&["list"])
+        .assert_failure(77)
+        .assert_stderr("Invalid token.");
+    assert!(env
+        .server
+        .requests()
+        .iter()
+        .all(|req| req.path != "/api/refresh"));
+}
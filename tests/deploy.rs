@@ -0,0 +1,314 @@
+//! Deploying apps and reading their logs, which the server streams.
+
+extern crate chrono;
+#[macro_use]
+extern crate serde_json;
+
+mod support;
+
+use std::fs;
+
+use chrono::Utc;
+use serde_json::Value;
+
+use support::*;
+
+fn with_app() -> TestEnv {
+    let env = TestEnv::new();
+    env.login();
+    env.server.add_app("myapp");
+    env.write_app_config(&json!({
+        "name": "myapp",
+        "createdAt": Utc::now(),
+        "command": ["npm", "start"]
+    }));
+    env
+}
+
+fn lines(lines: &[&str]) -> Vec<String> {
+    lines.iter().map(|line| line.to_string()).collect()
+}
+
+#[test]
+fn up_streams_build_output() {
+    let env = with_app();
+    fs::write(env.app_dir.join("index.js"), "console.log('hi')").unwrap();
+
+    env.run(&["up"])
+        .assert_success()
+        .assert_stdout("builder | Step 1/2 : FROM node:8")
+        .assert_stdout("builder | Step 2/2 : CMD npm start")
+        .assert_stdout("Deployed!");
+
+    // the app config and its files are uploaded together
+    let req = env.server.last_request("POST", "/api/apps/myapp/up/");
+    let body = req.body_text();
+    assert!(req
+        .header("content-type")
+        .unwrap()
+        .starts_with("multipart/form-data"));
+    assert!(body.contains("name=\"config\""));
+    assert!(body.contains("\"command\":[\"npm\",\"start\"]"));
+    assert!(body.contains("name=\"file\""));
+    assert!(body.contains("console.log('hi')"));
+}
+
+#[test]
+fn up_shows_build_errors() {
+    let env = with_app();
+    env.server.state().deploy_lines = lines(&[
+        "[stdout] Step 1/2 : FROM node:8",
+        "[stderr] npm ERR! missing script: start",
+        "[result] FAILED",
+    ]);
+
+    env.run(&["up"])
+        .assert_failure(1)
+        .assert_stderr("Failed to deploy.")
+        .assert_stderr("builder | npm ERR! missing script: start");
+}
+
+#[test]
+fn up_with_unexpected_output() {
+    let env = with_app();
+    env.server.state().deploy_lines = lines(&["no space left on device"]);
+
+    env.run(&["up"])
+        .assert_failure(1)
+        .assert_stderr("Failed to deploy:\nno space left on device");
+}
+
+#[test]
+fn up_needs_payment_method() {
+    let env = with_app();
+    env.server.state().has_card = false;
+
+    env.run(&["up"])
+        .assert_failure(1)
+        .assert_stderr("Missing payment method.");
+    assert!(env
+        .server
+        .requests()
+        .iter()
+        .all(|req| !req.path.ends_with("/up/")));
+}
+
+/// An app mounting `files`, with a `db` credentials secret and a `token` raw one.
+fn with_mounts(files: Value) -> TestEnv {
+    let env = with_app();
+    env.server.state().secrets = vec![
+        json!({ "name": "db", "type": "credentials", "createdAt": "2018-06-01T12:00:00Z" }),
+        json!({ "name": "token", "type": "raw", "createdAt": "2018-06-01T12:00:00Z" }),
+    ];
+    env.write_app_config(&json!({
+        "name": "myapp",
+        "createdAt": Utc::now(),
+        "files": files
+    }));
+    env
+}
+
+fn assert_not_deployed(env: &TestEnv) {
+    assert!(env
+        .server
+        .requests()
+        .iter()
+        .all(|req| !req.path.ends_with("/up/")));
+}
+
+#[test]
+fn up_with_mounted_secrets() {
+    let env = with_mounts(json!([
+        { "secret": "db", "key": "password", "path": "/etc/db/password" },
+        { "secret": "token", "path": "/etc/token" }
+    ]));
+    env.run(&["up"]).assert_success();
+    let body = env
+        .server
+        .last_request("POST", "/api/apps/myapp/up/")
+        .body_text();
+    assert!(body.contains("\"path\":\"/etc/db/password\""), "{}", body);
+}
+
+#[test]
+fn up_with_relative_mount_path() {
+    let env = with_mounts(json!([{ "secret": "token", "path": "etc/token" }]));
+    env.run(&["up"])
+        .assert_failure(1)
+        .assert_stderr("Invalid path for secret token: etc/token must be absolute.");
+    assert_not_deployed(&env);
+}
+
+#[test]
+fn up_with_path_mounted_twice() {
+    let env = with_mounts(json!([
+        { "secret": "db", "key": "password", "path": "/etc/secret" },
+        { "secret": "token", "path": "/etc/secret" }
+    ]));
+    env.run(&["up"])
+        .assert_failure(1)
+        .assert_stderr("Path /etc/secret is mounted more than once.");
+    assert_not_deployed(&env);
+}
+
+#[test]
+fn up_with_missing_secret() {
+    let env = with_mounts(json!([{ "secret": "api-key", "path": "/etc/api-key" }]));
+    env.run(&["up"])
+        .assert_failure(1)
+        .assert_stderr("Secret api-key does not exist.")
+        .assert_stderr("deployc secret create");
+    assert_not_deployed(&env);
+}
+
+#[test]
+fn up_with_unknown_secret_key() {
+    let env = with_mounts(json!([{ "secret": "db", "key": "pass", "path": "/etc/db" }]));
+    env.run(&["up"])
+        .assert_failure(1)
+        .assert_stderr("Secret db has no key pass.")
+        .assert_stderr("Expected one of: username, password.");
+    assert_not_deployed(&env);
+
+    let env = with_mounts(json!([{ "secret": "token", "key": "value", "path": "/etc/token" }]));
+    env.run(&["up"])
+        .assert_failure(1)
+        .assert_stderr("Secret token has no key value.")
+        .assert_stderr("raw secrets have no keys.");
+    assert_not_deployed(&env);
+}
+
+#[test]
+fn logs() {
+    let env = with_app();
+    env.server.state().log_lines = lines(&[
+        "web|2018-06-01T12:00:00Z Listening on :8080",
+        "web|2018-06-01T12:00:01Z GET / 200",
+    ]);
+
+    env.run(&["logs", "--tail", "2"])
+        .assert_success()
+        .assert_stdout("Listening on :8080")
+        .assert_stdout("GET / 200");
+
+    let req = env.server.last_request("GET", "/api/apps/myapp/logs/");
+    assert!(req.query.contains("follow=0"));
+    assert!(req.query.contains("tail=2"));
+}
+
+#[test]
+fn logs_renew_expired_session() {
+    let env = with_app();
+    env.server.state().log_lines = lines(&["web|2018-06-01T12:00:00Z Listening on :8080"]);
+    {
+        let mut state = env.server.state();
+        state.tokens.retain(|token| token != SESSION_TOKEN);
+        state.refreshable.push(SESSION_TOKEN.to_string());
+    }
+
+    env.run(&["logs"])
+        .assert_success()
+        .assert_stdout("Listening on :8080");
+    let req = env.server.last_request("GET", "/api/apps/myapp/logs/");
+    assert_eq!(
+        req.header("authorization"),
+        Some(format!("Bearer {}", REFRESHED_TOKEN).as_str())
+    );
+    assert_eq!(env.token().as_deref(), Some(REFRESHED_TOKEN));
+}
+
+#[test]
+fn logs_follow_stops_when_session_expires() {
+    let env = with_app();
+    env.server.state().tokens.clear();
+
+    // can't be refreshed, and there is no one to log in again
+    env.run(&["logs", "--follow"])
+        .assert_failure(77)
+        .assert_stderr("Session expired.");
+}
+
+#[test]
+fn logs_as_json() {
+    let env = with_app();
+    env.server.state().log_lines = lines(&["web|2018-06-01T12:00:00Z Listening on :8080"]);
+
+    let run = env.run(&["logs", "--output", "json"]);
+    run.assert_success();
+    let line: serde_json::Value = serde_json::from_str(run.stdout.trim()).unwrap();
+    assert_eq!(
+        line,
+        json!({
+            "source": "web",
+            "timestamp": "2018-06-01T12:00:00Z",
+            "message": "Listening on :8080"
+        })
+    );
+}
+
+#[test]
+fn logs_save_size_overflow() {
+    let env = with_app();
+    env.run(&["logs", "--save", "app.log", "--max-size", "99999999999G"])
+        .assert_failure(1)
+        .assert_stderr("Invalid size: 99999999999G");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn logs_save_failure() {
+    let env = with_app();
+    env.server.state().log_lines = lines(&["web|2018-06-01T12:00:00Z Listening on :8080"]);
+
+    // a full disk is not a lost connection
+    env.run(&["logs", "--save", "/dev/full"])
+        .assert_failure(1)
+        .assert_stderr("Could not save logs to /dev/full");
+}
+
+#[test]
+fn logs_since_too_long_ago() {
+    let env = with_app();
+    for since in &["99999999999999999999s", "999999999999999d"] {
+        env.run(&["logs", "--since", since])
+            .assert_failure(1)
+            .assert_stderr(&format!("Invalid time: {}", since));
+    }
+    assert!(env
+        .server
+        .requests()
+        .iter()
+        .all(|req| !req.path.ends_with("/logs/")));
+}
+
+#[test]
+fn logs_since_invalid_duration() {
+    let env = with_app();
+    for since in &["0x", "5", "1w", "yesterday"] {
+        env.run(&["logs", "--since", since])
+            .assert_failure(1)
+            .assert_stderr(&format!("Invalid time: {}", since))
+            .assert_stderr("Use a duration such as 30s");
+    }
+    assert!(env
+        .server
+        .requests()
+        .iter()
+        .all(|req| !req.path.ends_with("/logs/")));
+}
+
+#[test]
+fn logs_by_level_keep_unstructured_lines() {
+    let env = with_app();
+    env.server.state().log_lines = lines(&[
+        r#"web|2018-06-01T12:00:00Z {"level":"info","msg":"Listening"}"#,
+        r#"web|2018-06-01T12:00:01Z {"level":"error","msg":"Request failed"}"#,
+        "web|2018-06-01T12:00:02Z thread 'main' panicked at 'oops'",
+    ]);
+
+    let run = env.run(&["logs", "--level", "error"]);
+    run.assert_success()
+        .assert_stdout("Request failed")
+        .assert_stdout("panicked at 'oops'");
+    assert!(!run.stdout.contains("Listening"), "{}", run);
+}
@@ -0,0 +1,247 @@
+//! `--output` formats of the read commands, and colors.
+
+extern crate chrono;
+#[macro_use]
+extern crate serde_json;
+extern crate serde_yaml;
+
+mod support;
+
+use std::process::Stdio;
+
+use chrono::Utc;
+use serde_json::Value;
+
+use support::*;
+
+fn with_apps() -> TestEnv {
+    let env = TestEnv::new();
+    env.login();
+    env.server.state().apps = vec![
+        json!({ "name": "api", "createdAt": "2018-06-01T12:00:00Z", "tier": "pro" }),
+        json!({ "name": "myapp", "createdAt": "2018-06-02T12:00:00Z", "tier": "free" }),
+    ];
+    env.write_app_config(&json!({ "name": "myapp", "createdAt": "2018-06-02T12:00:00Z" }));
+    env
+}
+
+fn json(run: &Run) -> Value {
+    run.assert_success();
+    serde_json::from_str(&run.stdout).unwrap_or_else(|_| panic!("stdout is not JSON:\n{}", run))
+}
+
+#[test]
+fn list_as_json() {
+    let env = with_apps();
+    assert_eq!(
+        json(&env.run(&["list", "--output", "json"])),
+        json!([
+            { "name": "myapp", "tier": "free", "createdAt": "2018-06-02T12:00:00Z" },
+            { "name": "api", "tier": "pro", "createdAt": "2018-06-01T12:00:00Z" }
+        ])
+    );
+}
+
+#[test]
+fn list_as_yaml() {
+    let env = with_apps();
+    let run = env.run(&["list", "-o", "yaml"]);
+    run.assert_success();
+    let apps: Value = serde_yaml::from_str(&run.stdout).unwrap();
+    assert_eq!(apps[0]["name"], "myapp");
+    assert_eq!(apps[1]["tier"], "pro");
+}
+
+#[test]
+fn list_names() {
+    let env = with_apps();
+    let run = env.run(&["list", "-o", "name"]);
+    run.assert_success();
+    assert_eq!(run.stdout, "myapp\napi\n");
+}
+
+/// Runs `deployc` with its stdout closed, as `deployc list | head -0` would
+/// before the output has been fetched.
+fn run_into_closed_pipe(env: &TestEnv, args: &[&str]) {
+    let mut child = env
+        .command(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    drop(child.stdout.take());
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(0), "{:?}: {}", args, stderr);
+    assert_eq!(stderr, "", "{:?}", args);
+}
+
+#[test]
+fn output_into_closed_pipe() {
+    let env = with_apps();
+    run_into_closed_pipe(&env, &["list"]);
+    run_into_closed_pipe(&env, &["list", "-o", "json"]);
+    run_into_closed_pipe(&env, &["list", "-o", "name"]);
+    run_into_closed_pipe(&env, &["describe"]);
+    run_into_closed_pipe(&env, &["describe", "-o", "table"]);
+}
+
+#[test]
+fn list_wide() {
+    let env = with_apps();
+    env.run(&["list", "-o", "wide"])
+        .assert_success()
+        .assert_stdout("Created At")
+        .assert_stdout("myapp");
+    let run = env.run(&["list"]);
+    run.assert_success();
+    assert!(!run.stdout.contains("Created At"), "{}", run);
+}
+
+#[test]
+fn empty_list_as_json() {
+    let env = TestEnv::new();
+    env.login();
+    assert_eq!(json(&env.run(&["list", "-o", "json"])), json!([]));
+}
+
+#[test]
+fn output_before_subcommand() {
+    let env = with_apps();
+    env.server.state().env = json!({ "PORT": "8080" });
+    let expected = json!([{ "name": "PORT", "value": "8080" }]);
+    assert_eq!(json(&env.run(&["-o", "json", "env", "list"])), expected);
+    assert_eq!(json(&env.run(&["env", "-o", "json", "list"])), expected);
+    assert_eq!(json(&env.run(&["env", "list", "-o", "json"])), expected);
+    assert_eq!(json(&env.run(&["env", "-o", "json"])), expected);
+}
+
+#[test]
+fn env_list_as_json() {
+    let env = with_apps();
+    env.server.state().env = json!({
+        "NODE_ENV": "production",
+        "DATABASE_URL": { "secret": "db" }
+    });
+    // sorted by name
+    assert_eq!(
+        json(&env.run(&["env", "list", "-o", "json"])),
+        json!([
+            { "name": "DATABASE_URL", "value": { "secret": "db" } },
+            { "name": "NODE_ENV", "value": "production" }
+        ])
+    );
+}
+
+#[test]
+fn secrets_list_formats() {
+    let env = with_apps();
+    env.server.state().secrets = vec![json!({
+        "name": "db",
+        "type": "credentials",
+        "createdAt": "2018-06-01T12:00:00Z"
+    })];
+
+    assert_eq!(
+        json(&env.run(&["secrets", "list", "-o", "json"])),
+        json!([{ "name": "db", "type": "credentials", "createdAt": "2018-06-01T12:00:00Z" }])
+    );
+    env.run(&["secrets", "list", "-o", "wide"])
+        .assert_success()
+        .assert_stdout("username, password");
+}
+
+#[test]
+fn tier_formats() {
+    let env = with_apps();
+    assert_eq!(
+        json(&env.run(&["tier", "-o", "json"])),
+        json!({ "name": "free", "pricing": "$0/month" })
+    );
+    let run = env.run(&["tier", "-o", "name"]);
+    run.assert_success();
+    assert_eq!(run.stdout, "free\n");
+}
+
+#[test]
+fn describe_formats() {
+    let env = TestEnv::new();
+    env.login();
+    env.write_app_config(&json!({
+        "name": "myapp",
+        "createdAt": Utc::now(),
+        "command": ["npm", "start"],
+        "port": 8080
+    }));
+
+    // JSON unless asked otherwise, as it always was
+    let app = json(&env.run(&["describe"]));
+    assert_eq!(app["name"], "myapp");
+    assert_eq!(app["command"], json!(["npm", "start"]));
+
+    env.run(&["describe", "-o", "table"])
+        .assert_success()
+        .assert_stdout("Name: myapp")
+        .assert_stdout("Command: npm start")
+        .assert_stdout("Port: 8080");
+    let app: Value = serde_yaml::from_str(&env.run(&["describe", "-o", "yaml"]).stdout).unwrap();
+    assert_eq!(app["port"], 8080);
+}
+
+#[test]
+fn logs_as_text_or_json_only() {
+    let env = with_apps();
+    env.server.state().log_lines = vec!["web|2018-06-01T12:00:00Z Listening".to_string()];
+
+    env.run(&["logs", "-o", "text"])
+        .assert_success()
+        .assert_stdout("Listening");
+    env.run(&["-o", "json", "logs"])
+        .assert_success()
+        .assert_stdout("\"message\":\"Listening\"");
+    env.run(&["logs", "-o", "yaml"])
+        .assert_failure(1)
+        .assert_stderr("Logs can only be output as text or json.");
+}
+
+#[test]
+fn invalid_output_format() {
+    let env = TestEnv::new();
+    let run = env.run(&["list", "-o", "xml"]);
+    assert_eq!(run.code, Some(1), "{}", run);
+    assert!(run.stderr.contains("isn't a valid value"), "{}", run);
+}
+
+#[test]
+fn output_format_of_other_commands() {
+    let env = TestEnv::new();
+    env.run(&["logout", "-o", "json"])
+        .assert_failure(1)
+        .assert_stderr("logout has no output format.");
+    env.run(&["-o", "json", "tier", "upgrade"])
+        .assert_failure(1)
+        .assert_stderr("tier upgrade has no output format.");
+}
+
+#[test]
+fn errors_are_not_output() {
+    let env = TestEnv::new();
+    let run = env.run(&["list", "-o", "json"]);
+    run.assert_failure(1).assert_stderr("error: Not logged in.");
+    assert_eq!(run.stdout, "", "{}", run);
+}
+
+#[test]
+fn no_colors_when_piped() {
+    let env = TestEnv::new();
+    let run = env.run(&["list"]);
+    run.assert_failure(1).assert_stderr("error: Not logged in.");
+    assert!(!run.stderr.contains('\x1b'), "{}", run);
+
+    let output = env.command(&["list"])
+        .env("CLICOLOR_FORCE", "1")
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&output.stderr).contains('\x1b'));
+}
@@ -0,0 +1,117 @@
+//! Profiles, their TLS settings and endpoints, and the global options.
+
+extern crate chrono;
+#[macro_use]
+extern crate serde_json;
+
+mod support;
+
+use std::fs;
+
+use chrono::{Duration, Utc};
+
+use support::*;
+
+#[test]
+fn set_tls_settings_of_default_profile() {
+    let env = TestEnv::new();
+    let bundle = env.home.join("ca.pem");
+    fs::write(&bundle, "").unwrap();
+
+    env.run(&[
+        "profile",
+        "set",
+        "--ca-bundle",
+        bundle.to_str().unwrap(),
+        "--skip-hostname-verification",
+    ])
+    .assert_success()
+    .assert_stdout("Profile default updated.");
+    let profile = env.config();
+    assert_eq!(profile["endpoint"], format!("{}api/", env.server.url));
+    assert_eq!(
+        profile["caBundle"],
+        bundle.canonicalize().unwrap().to_str().unwrap()
+    );
+    assert_eq!(profile["skipHostnameVerification"], true);
+
+    env.run(&[
+        "profile",
+        "set",
+        "default",
+        "--no-ca-bundle",
+        "--verify-hostname",
+    ])
+    .assert_success();
+    let profile = env.config();
+    assert!(profile.get("caBundle").is_none());
+    assert!(profile.get("skipHostnameVerification").is_none());
+}
+
+#[test]
+fn set_needs_a_setting() {
+    let env = TestEnv::new();
+    env.run(&["profile", "set"])
+        .assert_failure(1)
+        .assert_stderr("Nothing to set.");
+    assert!(!env.home.join(".deployc/config.json").exists());
+}
+
+#[test]
+fn set_unknown_profile() {
+    let env = TestEnv::new();
+    env.run(&["profile", "set", "staging", "--verify-hostname"])
+        .assert_failure(1)
+        .assert_stderr("No profile named staging.");
+}
+
+#[test]
+fn endpoint_from_env_overrides_profile() {
+    let env = TestEnv::new();
+    let issued_at = Utc::now() - Duration::days(5);
+    let stored = "http://127.0.0.1:1/api/";
+    env.write_config(
+        json!({
+            "endpoint": stored,
+            "tokenType": "session",
+            "tokenIssuedAt": issued_at,
+            "tokenExpiresAt": issued_at + Duration::days(7)
+        }),
+        SESSION_TOKEN,
+    );
+
+    // DEPLOYC_ENDPOINT is the test server; the session is refreshed and stored
+    env.run(&["list"]).assert_success();
+    env.server.last_request("GET", "/api/apps/");
+    assert_eq!(env.token().as_deref(), Some(REFRESHED_TOKEN));
+    assert_eq!(env.config()["endpoint"], stored);
+
+    env.run_with_env(&["list"], &[("DEPLOYC_ENDPOINT", "")])
+        .assert_failure(69);
+    env.run_with_env(&["list"], &[("DEPLOYC_ENDPOINT", "not a url")])
+        .assert_failure(1)
+        .assert_stderr("Invalid endpoint in DEPLOYC_ENDPOINT.");
+}
+
+#[test]
+fn global_options_after_subcommand() {
+    let env = TestEnv::new();
+    env.login();
+
+    env.run(&["list", "--profile", "staging"])
+        .assert_failure(1)
+        .assert_stderr("No profile named staging.");
+    env.run(&["env", "list", "-p", "staging"])
+        .assert_failure(1)
+        .assert_stderr("No profile named staging.");
+    env.run(&["list", "--timeout", "soon"])
+        .assert_failure(1)
+        .assert_stderr("Invalid timeout: soon.");
+    env.run(&["list", "--retries", "many"])
+        .assert_failure(1)
+        .assert_stderr("Invalid number of retries: many.");
+    env.run(&["list", "--verbose"])
+        .assert_success()
+        .assert_stderr("debug:")
+        .assert_stderr("/api/apps/");
+}
@@ -0,0 +1,622 @@
+//! A stand-in for the deployc.io API that runs in the test process, and
+//! helpers for running the `deployc` binary against it from a home
+//! directory of its own.
+
+#![allow(dead_code)]
+
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::process::{self, Command, Output, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::{env, thread};
+
+use chrono::{Duration, Utc};
+use serde_json::{self, Value};
+
+pub const USERNAME: &str = "alice";
+pub const PASSWORD: &str = "hunter2";
+/// Needs the two-factor code `OTP` on top of the password.
+pub const OTP_USERNAME: &str = "bob";
+pub const OTP: &str = "123456";
+/// Already taken, so signing up with it fails.
+pub const TAKEN_USERNAME: &str = "taken";
+
+pub const SESSION_TOKEN: &str = "session-token";
+pub const REFRESHED_TOKEN: &str = "refreshed-token";
+pub const API_TOKEN: &str = "api-token";
+pub const API_TOKEN_NAME: &str = "ci";
+
+static NEXT_DIR: AtomicUsize = AtomicUsize::new(0);
+
+/// A request the server received.
+#[derive(Clone)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// A header's value, by its lowercase name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn token(&self) -> Option<&str> {
+        self.header("authorization")
+            .and_then(|auth| auth.split_whitespace().nth(1))
+    }
+
+    pub fn json(&self) -> Value {
+        serde_json::from_slice(&self.body).unwrap_or(Value::Null)
+    }
+
+    pub fn body_text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+enum Body {
+    Json(Value),
+    /// Sent a line at a time, as the log and deploy streams are.
+    Lines(Vec<String>),
+}
+
+struct Reply {
+    status: u16,
+    body: Body,
+}
+
+impl Reply {
+    fn ok(body: Value) -> Reply {
+        Reply {
+            status: 200,
+            body: Body::Json(body),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Reply {
+        Reply {
+            status,
+            body: Body::Json(json!({ "error": message })),
+        }
+    }
+
+    fn lines(lines: &[String]) -> Reply {
+        Reply {
+            status: 200,
+            body: Body::Lines(lines.to_vec()),
+        }
+    }
+}
+
+/// What the server knows about the account. Tests change it to set up
+/// the server's side of a scenario.
+pub struct State {
+    /// Tokens the server accepts, other than for logging in.
+    pub tokens: Vec<String>,
+    /// Tokens of expired sessions, which the server only accepts for refreshing.
+    pub refreshable: Vec<String>,
+    pub has_card: bool,
+    pub apps: Vec<Value>,
+    /// The environment of every app.
+    pub env: Value,
+    pub secrets: Vec<Value>,
+    pub tier: Value,
+    pub tiers: Vec<Value>,
+    pub log_lines: Vec<String>,
+    /// What a deploy streams back, with `[stdout]`, `[stderr]` and `[result]` prefixes.
+    pub deploy_lines: Vec<String>,
+    /// Sent with every reply, such as `X-API-Deprecated-Versions`.
+    pub headers: Vec<(String, String)>,
+    pub requests: Vec<Request>,
+}
+
+impl Default for State {
+    fn default() -> State {
+        State {
+            tokens: vec![SESSION_TOKEN.to_string(), API_TOKEN.to_string()],
+            refreshable: vec![],
+            has_card: true,
+            apps: vec![],
+            env: json!({}),
+            secrets: vec![],
+            tier: json!({ "name": "free", "pricing": "$0/month" }),
+            tiers: vec![
+                json!({ "name": "free", "pricing": "$0/month" }),
+                json!({ "name": "pro", "pricing": "$7/month" }),
+            ],
+            log_lines: vec![],
+            deploy_lines: vec![
+                "[stdout] Step 1/2 : FROM node:8".to_string(),
+                "[stdout] Step 2/2 : CMD npm start".to_string(),
+                "[result] SUCCESS".to_string(),
+            ],
+            headers: vec![],
+            requests: vec![],
+        }
+    }
+}
+
+fn session() -> Value {
+    let now = Utc::now();
+    json!({
+        "token": SESSION_TOKEN,
+        "issuedAt": now,
+        "expiresAt": now + Duration::days(7)
+    })
+}
+
+fn app(name: &str) -> Value {
+    json!({ "name": name, "createdAt": Utc::now(), "tier": "free" })
+}
+
+fn route(req: &Request, state: &mut State) -> Reply {
+    let segments: Vec<&str> = req
+        .path
+        .trim_matches('/')
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect();
+    let method = req.method.as_str();
+
+    match (method, &segments[..]) {
+        ("POST", &["api", "login"]) => {
+            let body = req.json();
+            let username = body["username"].as_str().unwrap_or("");
+            if body["password"] != PASSWORD || (username != USERNAME && username != OTP_USERNAME) {
+                return Reply::error(401, "Invalid username or password.");
+            }
+            if username == OTP_USERNAME && body["otp"] != OTP {
+                return Reply {
+                    status: 401,
+                    body: Body::Json(json!({
+                        "error": "Two-factor code required.",
+                        "code": "otp_required"
+                    })),
+                };
+            }
+            return Reply::ok(session());
+        }
+        ("POST", &["api", "signup"]) => {
+            let body = req.json();
+            if body["username"] == TAKEN_USERNAME {
+                return Reply {
+                    status: 422,
+                    body: Body::Json(json!({
+                        "error": "Could not sign up.",
+                        "fields": [{ "field": "username", "message": "is already taken" }]
+                    })),
+                };
+            }
+            let session = session();
+            return Reply::ok(json!({
+                "id": "u-1",
+                "username": body["username"],
+                "email": body["email"],
+                "token": session["token"],
+                "tokenIssuedAt": session["issuedAt"],
+                "tokenExpiresAt": session["expiresAt"]
+            }));
+        }
+        _ => {}
+    }
+
+    let token = req.token().unwrap_or("").to_string();
+    let refreshing = (method, &segments[..]) == ("GET", &["api", "refresh"][..])
+        && state.refreshable.contains(&token);
+    if !state.tokens.contains(&token) && !refreshing {
+        return Reply::error(401, "Invalid token.");
+    }
+
+    if let &["api", "apps", name, ..] = &segments[..] {
+        if !state.apps.iter().any(|app| app["name"] == name) {
+            return Reply::error(404, "App not found.");
+        }
+    }
+
+    match (method, &segments[..]) {
+        ("GET", &["api", "refresh"]) if token != API_TOKEN => {
+            state.tokens.push(REFRESHED_TOKEN.to_string());
+            let now = Utc::now();
+            Reply::ok(json!({
+                "token": REFRESHED_TOKEN,
+                "issuedAt": now,
+                "expiresAt": now + Duration::days(7)
+            }))
+        }
+        ("GET", &["api", "tokens", "current"]) if token == API_TOKEN => {
+            Reply::ok(json!({ "name": API_TOKEN_NAME }))
+        }
+        ("GET", &["api", "has-card"]) => Reply::ok(json!({ "exists": state.has_card })),
+        ("GET", &["api", "tiers"]) => Reply::ok(json!({ "tiers": state.tiers })),
+        ("GET", &["api", "apps"]) => Reply::ok(json!({ "apps": state.apps })),
+        ("POST", &["api", "apps"]) => {
+            let name = req.json()["name"].as_str().unwrap_or("").to_string();
+            state.apps.push(app(&name));
+            Reply::ok(json!({ "name": name, "tier": "free" }))
+        }
+        ("GET", &["api", "apps", _, "env"]) => Reply::ok(json!({ "env": state.env })),
+        ("POST", &["api", "apps", _, "env"]) => {
+            let body = req.json();
+            let key = body["key"].as_str().unwrap_or("").to_string();
+            state.env[key] = body["value"].clone();
+            Reply::ok(json!({ "env": state.env }))
+        }
+        ("GET", &["api", "apps", _, "secrets"]) => Reply::ok(json!({ "secrets": state.secrets })),
+        ("POST", &["api", "apps", _, "secrets"]) => {
+            let body = req.json();
+            let secret = json!({
+                "name": body["name"],
+                "type": body["type"],
+                "createdAt": Utc::now()
+            });
+            state.secrets.push(secret.clone());
+            Reply::ok(secret)
+        }
+        ("GET", &["api", "apps", _, "tier"]) => Reply::ok(state.tier.clone()),
+        ("GET", &["api", "apps", _, "logs"]) => Reply::lines(&state.log_lines),
+        ("POST", &["api", "apps", _, "up"]) => Reply::lines(&state.deploy_lines),
+        _ => Reply::error(404, "Not found."),
+    }
+}
+
+/// Reads a request, or `None` once the client closes the connection.
+fn read_request<R: BufRead>(reader: &mut R) -> Option<Request> {
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).unwrap_or(0) == 0 {
+        return None;
+    }
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let target = parts.next()?;
+    let (path, query) = match target.find('?') {
+        Some(i) => (&target[..i], &target[i + 1..]),
+        None => (target, ""),
+    };
+
+    let mut headers = vec![];
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).unwrap_or(0) == 0 {
+            return None;
+        }
+        if header.trim().is_empty() {
+            break;
+        }
+        if let Some(i) = header.find(':') {
+            headers.push((
+                header[..i].trim().to_lowercase(),
+                header[i + 1..].trim().to_string(),
+            ));
+        }
+    }
+
+    let mut req = Request {
+        method,
+        path: path.to_string(),
+        query: query.to_string(),
+        headers,
+        body: vec![],
+    };
+    if req.header("transfer-encoding") == Some("chunked") {
+        // multipart uploads are chunked
+        loop {
+            let mut size = String::new();
+            reader.read_line(&mut size).ok()?;
+            let size = usize::from_str_radix(size.trim(), 16).ok()?;
+            let mut chunk = vec![0; size + 2];
+            reader.read_exact(&mut chunk).ok()?;
+            if size == 0 {
+                break;
+            }
+            req.body.extend_from_slice(&chunk[..size]);
+        }
+    } else if let Some(len) = req.header("content-length") {
+        let mut body = vec![0; len.parse().ok()?];
+        reader.read_exact(&mut body).ok()?;
+        req.body = body;
+    }
+    Some(req)
+}
+
+fn write_reply(stream: &mut TcpStream, reply: Reply, headers: &[(String, String)]) -> bool {
+    let status = headers.iter().fold(
+        format!("HTTP/1.1 {} Stub", reply.status),
+        |status, (name, value)| format!("{}\r\n{}: {}", status, name, value),
+    );
+    let result = match reply.body {
+        Body::Json(body) => {
+            let body = body.to_string();
+            // in one write, so the response isn't held up by Nagle's algorithm
+            let response = format!(
+                "{}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes())
+        }
+        Body::Lines(lines) => {
+            let head = format!(
+                "{}\r\nContent-Type: text/plain\r\nTransfer-Encoding: chunked\r\n\r\n",
+                status
+            );
+            let mut result = stream.write_all(head.as_bytes());
+            for line in lines {
+                let line = format!("{}\n", line);
+                let chunk = format!("{:x}\r\n{}\r\n", line.len(), line);
+                result = result.and_then(|_| stream.write_all(chunk.as_bytes()));
+            }
+            result.and_then(|_| stream.write_all(b"0\r\n\r\n"))
+        }
+    };
+    result.and_then(|_| stream.flush()).is_ok()
+}
+
+/// Answers requests on a connection until the client closes it.
+fn serve(stream: TcpStream, state: Arc<Mutex<State>>) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut writer = stream;
+    while let Some(req) = read_request(&mut reader) {
+        let (reply, headers) = {
+            let mut state = state.lock().unwrap();
+            let reply = route(&req, &mut state);
+            state.requests.push(req);
+            (reply, state.headers.clone())
+        };
+        if !write_reply(&mut writer, reply, &headers) {
+            return;
+        }
+    }
+}
+
+/// The stub API server, listening on a free local port.
+pub struct Server {
+    pub url: String,
+    state: Arc<Mutex<State>>,
+}
+
+impl Server {
+    pub fn start() -> Server {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(State::default()));
+        let shared = state.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().filter_map(|stream| stream.ok()) {
+                let state = shared.clone();
+                thread::spawn(move || serve(stream, state));
+            }
+        });
+        Server { url, state }
+    }
+
+    pub fn add_app(&self, name: &str) {
+        self.state().apps.push(app(name));
+    }
+
+    /// Sends a header with every reply from now on.
+    pub fn add_header(&self, name: &str, value: &str) {
+        self.state()
+            .headers
+            .push((name.to_string(), value.to_string()));
+    }
+
+    pub fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+
+    /// The requests received so far.
+    pub fn requests(&self) -> Vec<Request> {
+        self.state().requests.clone()
+    }
+
+    /// The last request for `path`, which must have been made.
+    pub fn last_request(&self, method: &str, path: &str) -> Request {
+        self.requests()
+            .into_iter()
+            .rev()
+            .find(|req| req.method == method && req.path == path)
+            .unwrap_or_else(|| panic!("no {} {} request was made", method, path))
+    }
+}
+
+/// The outcome of running `deployc`.
+pub struct Run {
+    pub code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl Run {
+    fn from_output(output: Output) -> Run {
+        Run {
+            code: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        }
+    }
+
+    pub fn assert_success(&self) -> &Run {
+        assert_eq!(self.code, Some(0), "deployc failed:\n{}", self);
+        self
+    }
+
+    pub fn assert_failure(&self, code: i32) -> &Run {
+        assert_eq!(self.code, Some(code), "unexpected exit code:\n{}", self);
+        self
+    }
+
+    pub fn assert_stdout(&self, expected: &str) -> &Run {
+        assert!(
+            self.stdout.contains(expected),
+            "stdout is missing {:?}:\n{}",
+            expected,
+            self
+        );
+        self
+    }
+
+    pub fn assert_stderr(&self, expected: &str) -> &Run {
+        assert!(
+            self.stderr.contains(expected),
+            "stderr is missing {:?}:\n{}",
+            expected,
+            self
+        );
+        self
+    }
+}
+
+impl ::std::fmt::Display for Run {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(
+            f,
+            "exit code: {:?}\n--- stdout\n{}--- stderr\n{}",
+            self.code, self.stdout, self.stderr
+        )
+    }
+}
+
+/// A stub server and a temporary home directory, with an app directory in
+/// it to run commands from. Removed when dropped.
+pub struct TestEnv {
+    pub server: Server,
+    pub home: PathBuf,
+    pub app_dir: PathBuf,
+}
+
+impl TestEnv {
+    pub fn new() -> TestEnv {
+        let home = env::temp_dir().join(format!(
+            "deployc-test-{}-{}",
+            process::id(),
+            NEXT_DIR.fetch_add(1, Ordering::SeqCst)
+        ));
+        let app_dir = home.join("myapp");
+        fs::create_dir_all(&app_dir).unwrap();
+
+        TestEnv {
+            server: Server::start(),
+            home,
+            app_dir,
+        }
+    }
+
+    /// `deployc` with only the environment the test gives it, so the user's
+    /// own config, tokens and proxies are left out.
+    pub fn command(&self, args: &[&str]) -> Command {
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_deployc"));
+        cmd.args(args)
+            .current_dir(&self.app_dir)
+            .env_clear()
+            .env("HOME", &self.home)
+            .env("DEPLOYC_ENDPOINT", &self.server.url)
+            .env("DEPLOYC_CREDENTIAL_STORE", "file")
+            .env("DEPLOYC_RETRIES", "0");
+        cmd
+    }
+
+    pub fn run(&self, args: &[&str]) -> Run {
+        let output = self.command(args).stdin(Stdio::null()).output().unwrap();
+        Run::from_output(output)
+    }
+
+    /// Runs `deployc` with extra environment variables.
+    pub fn run_with_env(&self, args: &[&str], vars: &[(&str, &str)]) -> Run {
+        let mut cmd = self.command(args);
+        for &(name, value) in vars {
+            cmd.env(name, value);
+        }
+        Run::from_output(cmd.stdin(Stdio::null()).output().unwrap())
+    }
+
+    /// Runs `deployc` with `input` on stdin, as answers to its prompts.
+    pub fn run_with_input(&self, args: &[&str], input: &str) -> Run {
+        let mut child = self
+            .command(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input.as_bytes())
+            .unwrap();
+        Run::from_output(child.wait_with_output().unwrap())
+    }
+
+    pub fn login(&self) {
+        self.run_with_input(&["login"], &format!("{}\n{}\n", USERNAME, PASSWORD))
+            .assert_success();
+    }
+
+    fn config_dir(&self) -> PathBuf {
+        self.home.join(".deployc")
+    }
+
+    fn read_json(path: PathBuf) -> Value {
+        let f = File::open(&path).unwrap_or_else(|_| panic!("{} is missing", path.display()));
+        serde_json::from_reader(f).unwrap()
+    }
+
+    fn write_json(path: PathBuf, value: &Value) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        serde_json::to_writer_pretty(File::create(path).unwrap(), value).unwrap();
+    }
+
+    /// The default profile in `~/.deployc/config.json`.
+    pub fn config(&self) -> Value {
+        TestEnv::read_json(self.config_dir().join("config.json"))["profiles"]["default"].clone()
+    }
+
+    /// Sets the default profile, with its token in the credential file.
+    pub fn write_config(&self, config: Value, token: &str) {
+        TestEnv::write_json(
+            self.config_dir().join("config.json"),
+            &json!({ "profiles": { "default": config } }),
+        );
+        TestEnv::write_json(
+            self.config_dir().join("credentials.json"),
+            &json!({ "default": token }),
+        );
+    }
+
+    /// The default profile's token in `~/.deployc/credentials.json`.
+    pub fn token(&self) -> Option<String> {
+        let path = self.config_dir().join("credentials.json");
+        if !path.exists() {
+            return None;
+        }
+        TestEnv::read_json(path)["default"]
+            .as_str()
+            .map(|token| token.to_string())
+    }
+
+    /// The app's `deployc.json`.
+    pub fn app_config(&self) -> Value {
+        TestEnv::read_json(self.app_dir.join("deployc.json"))
+    }
+
+    pub fn write_app_config(&self, app: &Value) {
+        TestEnv::write_json(self.app_dir.join("deployc.json"), app);
+    }
+}
+
+impl Drop for TestEnv {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.home);
+    }
+}